@@ -1,3 +1,23 @@
 fn main() {
     built::write_built_file().expect("Failed to acquire build-time information");
+    println!("cargo:rustc-env=JJ_LIB_VERSION={}", jj_lib_version());
+}
+
+/// The version of jj-lib that was resolved in the lock file.
+fn jj_lib_version() -> String {
+    let lock = std::env::var("CARGO_MANIFEST_DIR")
+        .ok()
+        .and_then(|dir| std::fs::read_to_string(std::path::Path::new(&dir).join("Cargo.lock")).ok())
+        .unwrap_or_default();
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line == "name = \"jj-lib\""
+            && let Some(version) = lines
+                .next()
+                .and_then(|line| line.strip_prefix("version = "))
+        {
+            return version.trim_matches('"').to_string();
+        }
+    }
+    "unknown".to_string()
 }
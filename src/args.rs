@@ -10,9 +10,73 @@ pub enum CustomCommand {
 pub enum StarshipCommands {
     /// Print the configured Prompt
     Prompt {
+        /// Path to the jj-starship config file, `-` reads it from stdin
+        #[arg(long, env = "STARSHIP_JJ_CONFIG")]
+        starship_config: Option<PathBuf>,
+        /// How errors are reported on stderr
+        #[arg(long, value_enum, env = "STARSHIP_JJ_ERROR_FORMAT", default_value_t)]
+        error_format: ErrorFormat,
+        /// Print the config, the collected data and timings as JSON instead of the prompt.
+        /// Includes commit descriptions and bookmark names, check them before sharing.
+        #[arg(long)]
+        dump_data: bool,
+        /// Print the prompt as styled text, or the data the modules render as JSON
+        #[arg(long, value_enum, default_value_t, conflicts_with = "dump_data")]
+        format: PromptFormat,
+    },
+
+    /// Repeatedly re-render the prompt in place, for trying out config changes
+    Watch {
+        /// Path to the jj-starship config file
+        #[arg(long, env = "STARSHIP_JJ_CONFIG")]
+        starship_config: Option<PathBuf>,
+        /// Milliseconds between renders
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+        /// Only re-render when the config file changes
+        #[arg(long)]
+        watch_config: bool,
+    },
+
+    /// Check the config and the repo for problems and report what works and what doesn't
+    Doctor {
+        /// Path to the jj-starship config file, `-` reads it from stdin
+        #[arg(long, env = "STARSHIP_JJ_CONFIG")]
+        starship_config: Option<PathBuf>,
+    },
+
+    /// Time rendering the default prompt in a synthetic repo
+    Bench {
+        /// Number of commits in the repo
+        #[arg(long, default_value_t = 100)]
+        commits: usize,
+        /// Number of files in the repo
+        #[arg(long, default_value_t = 100)]
+        files: usize,
+        /// How often the prompt is rendered
+        #[arg(long, default_value_t = 20)]
+        runs: usize,
+    },
+
+    /// Print the bookmarks the prompt would show and their distance from the working copy
+    Bookmarks {
         /// Path to the jj-starship config file
         #[arg(long, env = "STARSHIP_JJ_CONFIG")]
         starship_config: Option<PathBuf>,
+        /// Print the bookmarks as a JSON object keyed by name
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Render the configured modules against a JSON description of the repo state instead of a repo
+    #[command(hide = true)]
+    Render {
+        /// Path to the jj-starship config file
+        #[arg(long, env = "STARSHIP_JJ_CONFIG")]
+        starship_config: Option<PathBuf>,
+        /// JSON file with the data the modules render, e.g. `{"commit": {"desc": "wip"}}`
+        #[arg(long)]
+        fixture: PathBuf,
     },
 
     /// Interact with the configuration
@@ -20,12 +84,44 @@ pub enum StarshipCommands {
     Config(ConfigCommands),
 }
 
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum PromptFormat {
+    /// The prompt with ANSI escape sequences
+    #[default]
+    Ansi,
+    /// The data the configured modules render, for custom prompt renderers
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// jj's usual error messages
+    #[default]
+    Human,
+    /// A single JSON object per error
+    Json,
+}
+
 #[derive(Debug, Clone, clap::Subcommand)]
 pub enum ConfigCommands {
     /// Print the path to the config file
-    Path,
+    Path {
+        /// Print every source the prompt config is built from, highest precedence first
+        #[arg(long)]
+        resolve: bool,
+        /// Path to the jj-starship config file
+        #[arg(long, env = "STARSHIP_JJ_CONFIG")]
+        starship_config: Option<PathBuf>,
+    },
     /// Print the default Config
     Default,
+    /// Check that a config file is valid and print the modules it renders, without rendering a
+    /// prompt
+    Check {
+        /// Path to the jj-starship config file, `-` to read it from stdin
+        #[arg(env = "STARSHIP_JJ_CONFIG")]
+        path: Option<PathBuf>,
+    },
 }
 
 #[derive(clap::Args, Clone, Debug)]
@@ -0,0 +1,214 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use jj_cli::command_error::{CommandError, user_error, user_error_with_message};
+use jj_lib::{
+    backend::{CopyId, TreeValue},
+    merge::Merge,
+    merged_tree::MergedTreeBuilder,
+    op_store::RefTarget,
+    ref_name::{RefName, WorkspaceName},
+    repo::Repo as _,
+    repo_path::RepoPathBuf,
+    settings::UserSettings,
+    workspace::Workspace,
+};
+use pollster::FutureExt as _;
+
+/// Renders the default prompt `runs` times in a synthetic repo with `commits` commits touching
+/// `files` files and prints how long the renders took.
+pub fn bench(
+    settings: &UserSettings,
+    commits: usize,
+    files: usize,
+    runs: usize,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let dir = std::env::temp_dir().join(format!("starship-jj-bench-{}", std::process::id()));
+    let result = bench_in(settings, &dir, commits, files, runs, io);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn bench_in(
+    settings: &UserSettings,
+    dir: &Path,
+    commits: usize,
+    files: usize,
+    runs: usize,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let repo_dir = dir.join("repo");
+    create_repo(settings, &repo_dir, commits, files)?;
+
+    // Always render the built-in defaults so results don't depend on the user's config.
+    let config_path = dir.join("starship-jj.toml");
+    let config = toml::to_string_pretty(&crate::config::Config::default()).map_err(user_error)?;
+    std::fs::write(&config_path, config)?;
+
+    let exe = std::env::current_exe()?;
+    let timings = measure(runs, || render(&exe, &repo_dir, &config_path))?;
+    write_report(io, commits, files, &timings)?;
+    Ok(())
+}
+
+/// Creates a repo with a linear history of `commits` commits, each changing one of `files` files,
+/// a `main` bookmark halfway through and a working copy commit with uncommitted changes on top.
+fn create_repo(
+    settings: &UserSettings,
+    path: &Path,
+    commits: usize,
+    files: usize,
+) -> Result<(), CommandError> {
+    std::fs::create_dir_all(path)?;
+    let (_workspace, repo) = Workspace::init_internal_git(settings, path)
+        .map_err(|err| user_error_with_message("Failed to create the benchmark repo", err))?;
+    let store = repo.store().clone();
+    let mut tx = repo.start_transaction();
+
+    let mut parent = store.root_commit_id().clone();
+    let mut tree_id = store.empty_merged_tree_id();
+    let files = files.max(1);
+    for i in 0..=commits {
+        let mut builder = MergedTreeBuilder::new(tree_id.clone());
+        // The first commit adds every file, later ones (and the working copy) change one each.
+        let changed = if i == 0 {
+            0..files
+        } else {
+            i % files..i % files + 1
+        };
+        for file in changed {
+            let path = RepoPathBuf::from_internal_string(format!("src/file{file}.txt"))
+                .map_err(user_error)?;
+            let contents = (0..=i)
+                .map(|line| format!("line {line}\n"))
+                .collect::<String>();
+            let id = store
+                .write_file(&path, &mut contents.as_bytes())
+                .block_on()?;
+            builder.set_or_remove(
+                path,
+                Merge::normal(TreeValue::File {
+                    id,
+                    executable: false,
+                    copy_id: CopyId::placeholder(),
+                }),
+            );
+        }
+        tree_id = builder.write_tree(&store)?;
+
+        let commit = tx
+            .repo_mut()
+            .new_commit(vec![parent], tree_id.clone())
+            .set_description(if i == commits {
+                String::new()
+            } else {
+                format!("commit {i}")
+            })
+            .write()?;
+        if i == commits / 2 {
+            tx.repo_mut().set_local_bookmark_target(
+                RefName::new("main"),
+                RefTarget::normal(commit.id().clone()),
+            );
+        }
+        if i == commits {
+            tx.repo_mut()
+                .edit(WorkspaceName::DEFAULT.to_owned(), &commit)
+                .map_err(user_error)?;
+        }
+        parent = commit.id().clone();
+    }
+    // Editing the working copy commit abandoned the empty one the repo was created with.
+    tx.repo_mut().rebase_descendants()?;
+    tx.commit("create benchmark repo").map_err(user_error)?;
+    Ok(())
+}
+
+fn render(exe: &Path, repo: &Path, config: &Path) -> Result<(), CommandError> {
+    let output = Command::new(exe)
+        // The working copy on disk was never checked out, so it must not be snapshotted.
+        .arg("--ignore-working-copy")
+        .arg("-R")
+        .arg(repo)
+        .args(["starship", "prompt", "--starship-config"])
+        .arg(config)
+        .output()?;
+    if !output.status.success() {
+        return Err(user_error(format!(
+            "Rendering the prompt failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Runs `f` `runs` times, returning how long each run took from fastest to slowest.
+fn measure(
+    runs: usize,
+    mut f: impl FnMut() -> Result<(), CommandError>,
+) -> Result<Vec<Duration>, CommandError> {
+    let mut timings = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        f()?;
+        timings.push(start.elapsed());
+    }
+    timings.sort();
+    Ok(timings)
+}
+
+/// The nearest-rank `percentile` of the sorted `timings`.
+fn percentile(timings: &[Duration], percentile: usize) -> Duration {
+    if timings.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (percentile * timings.len()).div_ceil(100).max(1);
+    timings[rank.min(timings.len()) - 1]
+}
+
+fn write_report(
+    io: &mut impl Write,
+    commits: usize,
+    files: usize,
+    timings: &[Duration],
+) -> std::io::Result<()> {
+    writeln!(
+        io,
+        "commits: {commits}, files: {files}, runs: {}",
+        timings.len()
+    )?;
+    for (name, p) in [
+        ("min", 0),
+        ("p50", 50),
+        ("p90", 90),
+        ("p99", 99),
+        ("max", 100),
+    ] {
+        let timing = percentile(timings, p);
+        writeln!(io, "{name}: {:.1}ms", timing.as_secs_f64() * 1000.0)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(ms: &[u64]) -> Vec<Duration> {
+        ms.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let timings = millis(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(percentile(&timings, 0), Duration::from_millis(1));
+        assert_eq!(percentile(&timings, 50), Duration::from_millis(5));
+        assert_eq!(percentile(&timings, 90), Duration::from_millis(9));
+        assert_eq!(percentile(&timings, 100), Duration::from_millis(10));
+    }
+}
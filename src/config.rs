@@ -1,37 +1,88 @@
 use std::{
+    collections::BTreeMap,
     io::Write,
+    ops::ControlFlow,
+    path::PathBuf,
     sync::{Arc, atomic::AtomicBool},
     time::Duration,
 };
 
+use age::Age;
+use author::Author;
 use bookmarks::Bookmarks;
+use cache::{DataCache, SnapshotStamp};
+use change_id::ChangeId;
 use commit::Commit;
-use jj_cli::command_error::CommandError;
+use commit_id::CommitId;
+use committer::Committer;
+use conflicts::Conflicts;
+use fetch_age::FetchAge;
+use file_count::FileCount;
+use focus::Focus;
+use heads::Heads;
+use hint::Hint;
+use jj_cli::command_error::{CommandError, user_error};
+use line_break::LineBreak;
+use locator::Locator;
 use metrics::Metrics;
+use notify::NotifyOn;
+use ok::OkModule;
+use operation_badge::OperationBadge;
+use path::Path;
+use powerline::Powerline;
 #[cfg(feature = "json-schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use state::State;
 use symbol::Symbol;
+pub(crate) use theme::resolve_styles;
+use upstream::Upstream;
+pub(crate) use upstream::UpstreamCounts;
+use util::Escape;
 #[cfg(not(feature = "json-schema"))]
 use util::Glob;
 
 pub mod util;
 
+mod age;
+mod author;
 mod bookmarks;
+mod cache;
+mod change_id;
 mod commit;
+mod commit_id;
+mod committer;
+mod conflicts;
+mod fetch_age;
+mod file_count;
+mod focus;
+mod heads;
+mod hint;
+mod line_break;
+mod locator;
 mod metrics;
+mod notify;
+mod ok;
+mod operation_badge;
+mod path;
+mod powerline;
 mod state;
 mod symbol;
+mod theme;
+mod upstream;
 
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Config {
+    /// Further config files layered on top of this one, in order. Relative paths are resolved
+    /// against the directory of this file.
+    #[serde(default)]
+    include: Vec<PathBuf>,
     #[serde(flatten, default)]
     global: GlobalConfig,
     /// Modules that will be rendered.
     #[serde(rename = "module", default = "default_modules")]
-    modules: Vec<ModuleConfig>,
+    modules: Vec<Module>,
 }
 
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
@@ -40,15 +91,120 @@ pub struct GlobalConfig {
     /// Text that will be printed between each Module.
     #[serde(default = "default_separator")]
     module_separator: String,
+    /// Text printed in place of `module_separator` where a module rendered nothing.
+    #[serde(default)]
+    empty_separator: Option<String>,
+    /// Do not print a separator after the last module that rendered something.
+    #[serde(default)]
+    trim_trailing_separator: bool,
+    /// Render each module's `placeholder` when it has nothing to show, so modules always take up space.
+    #[serde(default)]
+    always_render: bool,
+    /// Hide bookmarks on the commit the `Commit` module inherited its description from.
+    #[serde(default)]
+    hide_inherited_bookmark: bool,
+    /// Don't show the `empty` warning on a freshly created working copy commit (no changes, no
+    /// description and a single visible parent).
+    #[serde(default)]
+    hide_empty_on_fresh_commit: bool,
+    /// Apply the `compact` overrides when the terminal is narrower than this many columns.
+    #[serde(default)]
+    compact_below: Option<usize>,
+    /// Module options that override the configured ones in narrow terminals, keyed by module type.
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "BTreeMap<String, serde_json::Value>")
+    )]
+    compact: BTreeMap<String, toml::Value>,
     /// Timeout after which the process is teminated.
     #[serde(default)]
     timeout: Option<u64>,
+    /// Text that will be printed in place of the prompt when the `timeout` is hit, a single space
+    /// if unset.
+    #[serde(default)]
+    timeout_text: Option<String>,
     /// Controls the behaviour of the bookmark finding algorithm.
     #[serde(default)]
     pub bookmarks: BookmarkConfig,
     /// Controls whether color gets reset at the end.
     #[serde(default = "default_reset_color")]
     pub reset_color: bool,
+    /// Render `incompatible_text` instead of failing when the repo was written by an unsupported jj version.
+    #[serde(default = "default_catch_incompatible_repo")]
+    pub catch_incompatible_repo: bool,
+    /// Text that will be printed when the repo can't be opened by this version of jj.
+    #[serde(default = "default_incompatible_text")]
+    pub incompatible_text: String,
+    /// Text that will be printed instead of failing when the current directory is not in a jj repo.
+    #[serde(default)]
+    outside_repo_text: Option<String>,
+    /// Controls how the `outside_repo_text` is rendered.
+    #[serde(default)]
+    outside_repo_style: Option<util::Style>,
+    /// Escape characters in commit descriptions and bookmark names that the prompt would interpret.
+    #[serde(default)]
+    pub escape: Escape,
+    /// Send a terminal notification (OSC 9) when the working copy enters one of these states.
+    #[serde(default)]
+    pub notify_on: Vec<NotifyOn>,
+    /// Render only one module, or a text, instead of the whole prompt while the working copy is in
+    /// one of these states. The first matching entry wins.
+    #[serde(default)]
+    focus_on: Vec<Focus>,
+    /// Render the modules as powerline segments instead of separating them with `module_separator`.
+    #[serde(default)]
+    powerline: Option<Powerline>,
+    /// Text that will be printed before the modules, only if any of them rendered something.
+    #[serde(default)]
+    prompt_prefix: Option<String>,
+    /// Controls how the `prompt_prefix` is rendered.
+    #[serde(default)]
+    prompt_prefix_style: Option<util::Style>,
+    /// Text that will be printed after the modules, only if any of them rendered something.
+    #[serde(default)]
+    prompt_suffix: Option<String>,
+    /// Controls how the `prompt_suffix` is rendered.
+    #[serde(default)]
+    prompt_suffix_style: Option<util::Style>,
+    /// Maximum width of the whole prompt, longer prompts are cut off with an ellipsis.
+    #[serde(default)]
+    max_width: Option<usize>,
+    /// Don't snapshot the working copy again if the last snapshot is younger than this many
+    /// milliseconds, and render the tree it recorded instead. Changes made in the meantime show up
+    /// in the next prompt after the interval.
+    #[serde(default)]
+    snapshot_min_interval_ms: Option<u64>,
+    /// Store the data collected from the repo on disk and reuse it while the operation doesn't
+    /// change.
+    #[serde(default)]
+    cache: DataCache,
+    /// What to do when a single module fails, e.g. because a file in the diff can't be read.
+    #[serde(default)]
+    on_module_error: OnModuleError,
+    /// Text that will be printed in place of a failed module when `on_module_error` is `Glyph`.
+    #[serde(default = "default_module_error_glyph")]
+    module_error_glyph: String,
+    /// TOML file whose `[styles]` table defines named styles, e.g. `theme = "themes/dark.toml"`.
+    /// Relative paths are resolved against the directory of the config file.
+    #[serde(default)]
+    theme: Option<PathBuf>,
+    /// Named styles that can be referenced from any style with `style = "<name>"`, overriding the
+    /// ones from the `theme`. Keys set next to the reference take precedence.
+    #[serde(default)]
+    styles: BTreeMap<String, util::Style>,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OnModuleError {
+    /// Skip => Render nothing for the failed module
+    Skip,
+    /// Glyph => Render `module_error_glyph` in place of the failed module
+    Glyph,
+    /// Fail => [default] Fail the whole prompt
+    #[default]
+    Fail,
 }
 
 fn default_separator() -> String {
@@ -59,13 +215,25 @@ fn default_reset_color() -> bool {
     true
 }
 
-fn default_modules() -> Vec<ModuleConfig> {
+fn default_catch_incompatible_repo() -> bool {
+    true
+}
+
+fn default_incompatible_text() -> String {
+    "⚠ jj?".to_string()
+}
+
+fn default_module_error_glyph() -> String {
+    "⚠".to_string()
+}
+
+fn default_modules() -> Vec<Module> {
     vec![
-        ModuleConfig::Symbol(Default::default()),
-        ModuleConfig::Bookmarks(Default::default()),
-        ModuleConfig::Commit(Default::default()),
-        ModuleConfig::State(Default::default()),
-        ModuleConfig::Metrics(Default::default()),
+        ModuleConfig::Symbol(Default::default()).into(),
+        ModuleConfig::Bookmarks(Default::default()).into(),
+        ModuleConfig::Commit(Default::default()).into(),
+        ModuleConfig::State(Default::default()).into(),
+        ModuleConfig::Metrics(Default::default()).into(),
     ]
 }
 
@@ -82,6 +250,31 @@ pub struct BookmarkConfig {
     #[serde(default)]
     #[cfg(not(feature = "json-schema"))]
     pub exclude: Vec<Glob>,
+    /// Prefer the nearest ancestor bookmark matching this glob and render it first.
+    #[serde(default)]
+    #[cfg(feature = "json-schema")]
+    pub primary: Option<String>,
+    #[serde(default)]
+    #[cfg(not(feature = "json-schema"))]
+    pub primary: Option<Glob>,
+    /// Revset selecting the ancestor whose bookmarks are shown when `@` has none, replacing
+    /// `latest((heads(::@- & bookmarks())))`.
+    #[serde(default)]
+    pub target_revset: Option<String>,
+    /// What the distance of a remote bookmark like `main@origin` is measured from.
+    #[serde(default)]
+    pub remote_distance_base: RemoteDistanceBase,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteDistanceBase {
+    /// Head => [default] Count the commits between the remote bookmark and the working copy
+    #[default]
+    Head,
+    /// LocalBookmark => Count how far the remote bookmark lags behind its local bookmark, falling
+    /// back to the working copy if there is none or the local bookmark isn't ahead
+    LocalBookmark,
 }
 
 impl Default for BookmarkConfig {
@@ -89,6 +282,9 @@ impl Default for BookmarkConfig {
         Self {
             search_depth: default_search_depth(),
             exclude: Default::default(),
+            primary: Default::default(),
+            target_revset: None,
+            remote_distance_base: Default::default(),
         }
     }
 }
@@ -97,88 +293,730 @@ fn default_search_depth() -> usize {
     100
 }
 
+/// Deserializes a config file directly, for an error that points at the line it occurred on. The
+/// global options are checked on their own first, as flattening them into the `Config` loses the
+/// location of their errors.
+pub(crate) fn locate_error(config: &str) -> Option<toml::de::Error> {
+    toml::from_str::<GlobalConfig>(config)
+        .err()
+        .or_else(|| toml::from_str::<Config>(config).err())
+}
+
 impl Config {
+    /// Controls how the bookmarks shown in the prompt are found.
+    pub fn bookmarks(&self) -> &BookmarkConfig {
+        &self.global.bookmarks
+    }
+
+    /// The type of every configured module, in order.
+    pub fn module_types(&self) -> Vec<String> {
+        self.modules
+            .iter()
+            .map(|module| module.module.kind().to_string())
+            .collect()
+    }
+
+    /// Milliseconds after which the fallback is printed instead of the prompt.
+    pub fn timeout(&self) -> Option<u64> {
+        self.global.timeout
+    }
+
+    /// Merges the `compact` overrides into the modules if the terminal is narrower than `compact_below`.
+    pub fn apply_compact(&mut self, term_width: usize) -> Result<(), CommandError> {
+        if self
+            .global
+            .compact_below
+            .is_none_or(|compact_below| term_width >= compact_below)
+        {
+            return Ok(());
+        }
+
+        for module in &mut self.modules {
+            let mut value = toml::Value::try_from(&*module).map_err(user_error)?;
+            let Some(overrides) = value
+                .get("type")
+                .and_then(|kind| kind.as_str())
+                .and_then(|kind| self.global.compact.get(kind))
+            else {
+                continue;
+            };
+            merge_values(&mut value, overrides);
+            *module = value.try_into().map_err(user_error)?;
+        }
+        Ok(())
+    }
+
     pub fn print(
         &self,
         command_helper: &&jj_cli::cli_util::CommandHelper,
         state: &mut crate::State,
         data: &mut crate::JJData,
+        io: &mut impl Write,
+    ) -> Result<(), CommandError> {
+        self.print_with_timeout(command_helper, state, data, io, self.global.timeout)
+    }
+
+    /// Prints the prompt like `print`, but ignores the `timeout` and renders it however long that
+    /// takes, e.g. for `watch` which keeps running after the prompt.
+    pub fn print_untimed(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        io: &mut impl Write,
     ) -> Result<(), CommandError> {
+        self.print_with_timeout(command_helper, state, data, io, None)
+    }
+
+    fn print_with_timeout(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        io: &mut impl Write,
+        timeout: Option<u64>,
+    ) -> Result<(), CommandError> {
+        // Resolved before the timeout thread starts, so its fallback honors it too.
+        let color = color_enabled(command_helper);
+        // Kept apart from the prompt, so stripping its colors leaves the notifications intact.
+        let mut notifications = Vec::new();
+        let result = if color {
+            self.print_colored(
+                command_helper,
+                state,
+                data,
+                io,
+                &mut notifications,
+                color,
+                timeout,
+            )
+        } else {
+            let mut buffer = Vec::new();
+            let result = self.print_colored(
+                command_helper,
+                state,
+                data,
+                &mut buffer,
+                &mut notifications,
+                color,
+                timeout,
+            );
+            io.write_all(util::strip_ansi(&String::from_utf8_lossy(&buffer)).as_bytes())?;
+            result
+        };
+        io.write_all(&notifications)?;
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn print_colored(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        io: &mut impl Write,
+        notifications: &mut impl Write,
+        color: bool,
+        timeout: Option<u64>,
+    ) -> Result<(), CommandError> {
+        if command_helper.workspace_loader().is_err() && self.print_outside_repo(io)? {
+            return Ok(());
+        }
+
         let done = Arc::new(AtomicBool::new(false));
 
         let done2 = done.clone();
-        if let Some(timeout) = self.global.timeout {
+        if let Some(timeout) = timeout {
+            let text = self.global.timeout_text.clone();
             std::thread::spawn(move || {
                 std::thread::sleep(Duration::from_millis(timeout));
                 if !done2.load(std::sync::atomic::Ordering::Relaxed) {
-                    _ = util::Style::default().print(&mut std::io::stdout(), None, &mut None);
-                    print!(" ");
-                    let _ = std::io::stdout().flush();
+                    let _ = write_timeout_fallback(&mut std::io::stdout(), text.as_deref(), color);
                     std::process::exit(0);
                 }
             });
         }
-        let mut io = std::io::stdout();
+        let result = self.print_modules(command_helper, state, data, io, notifications);
+        done.store(true, std::sync::atomic::Ordering::Relaxed);
+        result
+    }
+
+    /// Collects the data the rendered modules need without printing anything.
+    pub fn collect(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+    ) -> Result<(), CommandError> {
+        let stamp = self.throttle_snapshot(command_helper, state);
+        focus::parse(
+            &self.global.focus_on,
+            command_helper,
+            state,
+            data,
+            &self.global,
+        )?;
+        let focus = focus::active(&self.global.focus_on, data);
+        let modules = self.rendered_modules(focus);
+        // Rendered like the prompt so the same modules are parsed, only the output is dropped.
+        self.render(
+            &modules,
+            focus,
+            &mut std::io::sink(),
+            |module, buffer, prev_style| {
+                self.render_module(module, command_helper, state, data, buffer, prev_style)?;
+                Ok(ControlFlow::Continue(()))
+            },
+        )?;
+        record_snapshot(stamp, state);
+        Ok(())
+    }
+
+    /// Skips the snapshot if the last one is younger than `snapshot_min_interval_ms`. Returns the
+    /// stamp to record once the workspace was snapshotted.
+    fn throttle_snapshot(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+    ) -> Option<SnapshotStamp> {
+        let interval = self.global.snapshot_min_interval_ms?;
+        let stamp = SnapshotStamp::new(command_helper)?;
+        if stamp.is_recent(interval) {
+            state.skip_snapshot();
+            return None;
+        }
+        Some(stamp)
+    }
+
+    /// Prints the modules from already collected data, without opening the repo.
+    pub fn print_data(
+        &self,
+        data: &crate::JJData,
+        io: &mut impl Write,
+    ) -> Result<(), CommandError> {
+        let focus = focus::active(&self.global.focus_on, data);
+        let modules = self.rendered_modules(focus);
+        self.render(&modules, focus, io, |module, buffer, prev_style| {
+            self.print_module(module, data, buffer, prev_style)?;
+            Ok(ControlFlow::Continue(()))
+        })
+    }
+
+    /// Renders `modules` into the prompt, with `render` writing a single module. `render` breaks to
+    /// replace the whole prompt with the `incompatible_text`.
+    fn render(
+        &self,
+        modules: &[&Module],
+        focus: Option<&Focus>,
+        io: &mut impl Write,
+        mut render: impl FnMut(
+            &Module,
+            &mut Vec<u8>,
+            &mut Option<nu_ansi_term::Style>,
+        ) -> Result<ControlFlow<()>, CommandError>,
+    ) -> Result<(), CommandError> {
         let mut prev_style = None;
-        for module in self.modules.iter() {
-            match module {
-                ModuleConfig::Bookmarks(bookmarks) => {
-                    bookmarks.parse(command_helper, state, data, &self.global)?;
-                    let mut io = io.lock();
-                    bookmarks.print(
-                        &mut io,
-                        data,
-                        &self.global.module_separator,
-                        &mut prev_style,
-                    )?;
-                }
-                ModuleConfig::Commit(commit_desc) => {
-                    commit_desc.parse(command_helper, state, data, &self.global)?;
-                    let mut io = io.lock();
-                    commit_desc.print(
-                        &mut io,
-                        data,
-                        &self.global.module_separator,
-                        &mut prev_style,
-                    )?
+        let mut body = Vec::new();
+        let mut writer = ModuleWriter::new(
+            &self.global.module_separator,
+            self.global.empty_separator.as_deref(),
+            self.global.trim_trailing_separator,
+        )
+        .powerline(self.global.powerline.as_ref());
+        for module in modules {
+            if matches!(module.module, ModuleConfig::LineBreak(_)) {
+                writer.line_break(&mut body, &mut prev_style)?;
+                continue;
+            }
+            if self.global.powerline.is_some() {
+                // Every segment starts from a reset, so styles can't carry over between modules.
+                prev_style = None;
+            }
+            // A module that fails may already have changed the style before its output is dropped.
+            let style_before = prev_style;
+            let mut buffer = Vec::new();
+            match render(module, &mut buffer, &mut prev_style) {
+                Ok(ControlFlow::Continue(())) => {}
+                Ok(ControlFlow::Break(())) => {
+                    util::Style::default().print(&mut body, None, &mut prev_style)?;
+                    write!(body, "{}", self.global.incompatible_text)?;
+                    writer = ModuleWriter::new("", None, true);
+                    break;
                 }
-                ModuleConfig::State(commit_warnings) => {
-                    commit_warnings.parse(command_helper, state, data, &self.global)?;
-                    let mut io = io.lock();
-                    commit_warnings.print(
-                        &mut io,
-                        data,
-                        &self.global.module_separator,
-                        &mut prev_style,
-                    )?
+                Err(err) => {
+                    prev_style = style_before;
+                    self.module_error(err, &mut buffer, &mut prev_style)?;
                 }
-                ModuleConfig::Metrics(commit_diff) => {
-                    commit_diff.parse(command_helper, state, data, &self.global)?;
-                    let mut io = io.lock();
-                    commit_diff.print(
-                        &mut io,
-                        data,
-                        &self.global.module_separator,
-                        &mut prev_style,
-                    )?
+            }
+            writer.write(&mut body, &buffer)?;
+        }
+        if let Some(focus) = focus
+            && modules.is_empty()
+        {
+            let mut buffer = Vec::new();
+            focus.print_text(&mut buffer, &mut prev_style)?;
+            writer.write(&mut body, &buffer)?;
+        }
+        writer.finish(&mut body)?;
+        self.write_prompt(io, &body)?;
+        if self.global.reset_color {
+            util::Style::default().print(io, None, &mut prev_style)?;
+        }
+        Ok(())
+    }
+
+    fn print_modules(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        io: &mut impl Write,
+        notifications: &mut impl Write,
+    ) -> Result<(), CommandError> {
+        let stamp = self.throttle_snapshot(command_helper, state);
+        let cache = self.global.cache.entry(command_helper, state, self);
+        let cached = cache.as_ref().and_then(|cache| cache.load());
+        let hit = cached.is_some();
+        if let Some(cached) = cached {
+            *data = cached;
+        }
+        if let Err(err) = focus::parse(
+            &self.global.focus_on,
+            command_helper,
+            state,
+            data,
+            &self.global,
+        ) && !(self.global.catch_incompatible_repo && state.is_incompatible())
+        {
+            return Err(err);
+        }
+        let focus = focus::active(&self.global.focus_on, data);
+        let modules = self.rendered_modules(focus);
+        for module in &modules {
+            // Failures resurface when the module itself is rendered.
+            if let Ok(true) = module.is_shown(command_helper, state) {
+                let _ = module.module.prefetch(command_helper, state, data);
+            }
+        }
+        self.render(
+            &modules,
+            focus,
+            io,
+            |module, buffer, prev_style| match self.render_module(
+                module,
+                command_helper,
+                state,
+                data,
+                buffer,
+                prev_style,
+            ) {
+                Ok(()) => Ok(ControlFlow::Continue(())),
+                Err(err) if self.global.catch_incompatible_repo && state.is_incompatible() => {
+                    eprintln!("starship-jj: {}", crate::state::format_error_chain(&err));
+                    Ok(ControlFlow::Break(()))
                 }
-                ModuleConfig::Symbol(symbol) => {
-                    symbol.parse(command_helper, state, data, &self.global)?;
-                    let mut io = io.lock();
-                    symbol.print(
-                        &mut io,
-                        data,
-                        &self.global.module_separator,
-                        &mut prev_style,
-                    )?
+                Err(err) => Err(err),
+            },
+        )?;
+        if let Some(cache) = cache
+            && !hit
+            && !state.is_incompatible()
+        {
+            cache.store(data);
+        }
+        record_snapshot(stamp, state);
+        if !state.is_incompatible() {
+            notify::notify(command_helper, state, data, &self.global, notifications)?;
+        }
+        Ok(())
+    }
+
+    fn render_module(
+        &self,
+        module: &Module,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        io: &mut impl Write,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        if !module.is_shown(command_helper, state)? {
+            return Ok(());
+        }
+        if module.no_cache {
+            module.module.invalidate(data);
+        }
+        if self.global.hide_inherited_bookmark
+            && matches!(module.module, ModuleConfig::Bookmarks(_))
+        {
+            // The bookmarks need to know where the description comes from before they're printed.
+            for commit in self
+                .modules
+                .iter()
+                .filter(|module| matches!(module.module, ModuleConfig::Commit(_)))
+            {
+                commit
+                    .module
+                    .parse(command_helper, state, data, &self.global)?;
+            }
+        }
+        module
+            .module
+            .parse(command_helper, state, data, &self.global)?;
+        self.print_module(module, data, io, prev_style)
+    }
+
+    /// The modules that are rendered: all of them, or only the one `focus` names. Empty if the focus
+    /// names no configured module, in which case its text is rendered instead.
+    fn rendered_modules(&self, focus: Option<&Focus>) -> Vec<&Module> {
+        let Some(focus) = focus else {
+            return self.modules.iter().collect();
+        };
+        focus
+            .module()
+            .and_then(|kind| {
+                self.modules
+                    .iter()
+                    .find(|module| module.module.kind() == kind)
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Wraps the rendered modules in `prompt_prefix` and `prompt_suffix`, unless nothing rendered,
+    /// and cuts the result off at `max_width`.
+    fn write_prompt(&self, io: &mut impl Write, body: &[u8]) -> Result<(), CommandError> {
+        if body.is_empty() {
+            return Ok(());
+        }
+        let mut prompt = Vec::new();
+        if let Some(prefix) = &self.global.prompt_prefix {
+            write_framing(
+                &mut prompt,
+                prefix,
+                self.global.prompt_prefix_style.as_ref(),
+            )?;
+        }
+        prompt.extend_from_slice(body);
+        if let Some(suffix) = &self.global.prompt_suffix {
+            write_framing(
+                &mut prompt,
+                suffix,
+                self.global.prompt_suffix_style.as_ref(),
+            )?;
+        }
+
+        let Some(max_width) = self.global.max_width else {
+            io.write_all(&prompt)?;
+            return Ok(());
+        };
+        let rendered = String::from_utf8_lossy(&prompt);
+        let truncated = util::truncate_ansi(&rendered, max_width, self.global.escape);
+        write!(io, "{truncated}")?;
+        Ok(())
+    }
+
+    /// Replaces the output of a module that failed according to `on_module_error`.
+    fn module_error(
+        &self,
+        err: CommandError,
+        buffer: &mut Vec<u8>,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        if self.global.on_module_error == OnModuleError::Fail {
+            return Err(err);
+        }
+        buffer.clear();
+        if self.global.on_module_error == OnModuleError::Glyph {
+            let style = util::Style {
+                color: Some(util::Color::Red),
+                ..Default::default()
+            };
+            style.print(buffer, None, prev_style)?;
+            write!(buffer, "{}", self.global.module_error_glyph)?;
+        }
+        Ok(())
+    }
+
+    /// Prints the `outside_repo_text`, returns whether there was one.
+    fn print_outside_repo(&self, io: &mut impl Write) -> Result<bool, CommandError> {
+        let Some(text) = &self.global.outside_repo_text else {
+            return Ok(false);
+        };
+        write_framing(io, text, self.global.outside_repo_style.as_ref())?;
+        Ok(true)
+    }
+
+    /// Prints an already parsed module, falling back to its placeholder if it rendered nothing.
+    fn print_module(
+        &self,
+        module: &Module,
+        data: &crate::JJData,
+        io: &mut impl Write,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let mut buffer = Vec::new();
+        module
+            .module
+            .print(&mut buffer, data, &self.global, prev_style)?;
+
+        if !buffer.is_empty() && (module.prefix.is_some() || module.suffix.is_some()) {
+            let rendered = String::from_utf8_lossy(&buffer).into_owned();
+            // The prefix goes after the module's leading style so it's rendered in it.
+            let (style, content) = rendered.split_at(util::visible_start(&rendered));
+            buffer = format!(
+                "{style}{}{content}{}",
+                module.prefix.as_deref().unwrap_or_default(),
+                module.suffix.as_deref().unwrap_or_default()
+            )
+            .into_bytes();
+        }
+
+        if buffer.is_empty()
+            && self.global.always_render
+            && let Some(placeholder) = &module.placeholder
+        {
+            module
+                .placeholder_style
+                .print(&mut buffer, default_placeholder_style(), prev_style)?;
+            write!(buffer, "{placeholder}")?;
+        }
+
+        match module.module_max_width {
+            Some(max_width) => {
+                let rendered = String::from_utf8_lossy(&buffer);
+                write!(
+                    io,
+                    "{}",
+                    util::truncate_ansi(&rendered, max_width, self.global.escape)
+                )?;
+            }
+            None => io.write_all(&buffer)?,
+        }
+        Ok(())
+    }
+}
+
+/// Whether the prompt may be colored: `NO_COLOR` and `--color never` turn colors off. jj's other
+/// color settings are ignored since the prompt never writes to a terminal directly.
+fn color_enabled(command_helper: &jj_cli::cli_util::CommandHelper) -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let never = matches!(
+        command_helper.global_args().early_args.color,
+        Some(jj_cli::ui::ColorChoice::Never)
+    );
+    !no_color && !never
+}
+
+/// Records that the working copy was just snapshotted, if it was.
+fn record_snapshot(stamp: Option<SnapshotStamp>, state: &crate::State) {
+    if let Some(stamp) = stamp
+        && state.has_snapshotted()
+    {
+        stamp.record();
+    }
+}
+
+/// Printed in place of the prompt when rendering it takes longer than the `timeout`.
+fn write_timeout_fallback(
+    io: &mut impl Write,
+    text: Option<&str>,
+    color: bool,
+) -> Result<(), CommandError> {
+    if color {
+        util::Style::default().print(io, None, &mut None)?;
+    }
+    write!(io, "{}", text.unwrap_or(" "))?;
+    io.flush()?;
+    Ok(())
+}
+
+/// Prints `text`, resetting its style afterwards so it doesn't leak into the modules.
+fn write_framing(
+    io: &mut impl Write,
+    text: &str,
+    style: Option<&util::Style>,
+) -> Result<(), CommandError> {
+    let Some(style) = style else {
+        write!(io, "{text}")?;
+        return Ok(());
+    };
+    let mut prev_style = None;
+    style.print(io, None, &mut prev_style)?;
+    write!(io, "{text}")?;
+    util::Style::default().print(io, None, &mut prev_style)?;
+    Ok(())
+}
+
+/// Recursively merges the tables in `overrides` into `base`, replacing any other values.
+fn merge_values(base: &mut toml::Value, overrides: &toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(base) => merge_values(base, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
                 }
             }
         }
-        if self.global.reset_color {
-            util::Style::default().print(&mut io, None, &mut prev_style)?;
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+/// Writes the rendered modules, placing separators only after modules that rendered something.
+struct ModuleWriter<'a> {
+    module_separator: &'a str,
+    empty_separator: Option<&'a str>,
+    /// Drop the separator owed after the last module.
+    trim_trailing: bool,
+    /// Separator owed after the last module that rendered something.
+    pending_separator: Option<&'a str>,
+    /// Replaces the separators with powerline glyphs if set.
+    powerline: Option<powerline::Segments<'a>>,
+}
+
+impl<'a> ModuleWriter<'a> {
+    fn new(
+        module_separator: &'a str,
+        empty_separator: Option<&'a str>,
+        trim_trailing: bool,
+    ) -> Self {
+        Self {
+            module_separator,
+            empty_separator,
+            trim_trailing,
+            pending_separator: None,
+            powerline: None,
+        }
+    }
+
+    fn powerline(mut self, powerline: Option<&'a Powerline>) -> Self {
+        self.powerline = powerline.map(powerline::Segments::new);
+        self
+    }
+
+    fn write(&mut self, io: &mut impl Write, module: &[u8]) -> std::io::Result<()> {
+        if module.is_empty() {
+            if self.pending_separator.is_some()
+                && let Some(empty_separator) = self.empty_separator
+            {
+                self.pending_separator = Some(empty_separator);
+            }
+            return Ok(());
+        }
+        if let Some(segments) = &mut self.powerline {
+            return segments.write(io, module);
         }
+
+        if let Some(separator) = self.pending_separator {
+            write!(io, "{separator}")?;
+        }
+        io.write_all(module)?;
+        self.pending_separator = Some(self.module_separator);
+        Ok(())
+    }
+
+    /// Ends the current line without the separator owed after its last module and resets the
+    /// style, so neither carries over to the next line.
+    fn line_break(
+        &mut self,
+        io: &mut impl Write,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        if let Some(segments) = &mut self.powerline {
+            segments.line_break(io)?;
+        }
+        self.pending_separator = None;
+        if prev_style.is_some() {
+            util::Style::default().print(io, None, prev_style)?;
+        }
+        *prev_style = None;
+        writeln!(io)?;
         Ok(())
     }
+
+    fn finish(&mut self, io: &mut impl Write) -> std::io::Result<()> {
+        if let Some(segments) = &mut self.powerline {
+            return segments.finish(io);
+        }
+        if let Some(separator) = self.pending_separator.take()
+            && !self.trim_trailing
+        {
+            write!(io, "{separator}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A module together with the options shared by all modules.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+struct Module {
+    #[serde(flatten)]
+    module: ModuleConfig,
+    /// Always recompute the data this module renders instead of reusing cached data.
+    #[serde(default)]
+    no_cache: bool,
+    /// Only render this module while the working copy commit is in this revset, e.g.
+    /// `conflicts() | divergent()`. An invalid revset fails the module when the prompt is rendered.
+    /// Fixtures have no repo to evaluate it in, so they render the module regardless.
+    #[serde(default)]
+    when: Option<String>,
+    /// Text that will be printed when this module renders nothing and `always_render` is set.
+    #[serde(default)]
+    placeholder: Option<String>,
+    /// Controls how the placeholder is rendered.
+    #[serde(default = "default_placeholder_style")]
+    placeholder_style: util::Style,
+    /// Maximum width of everything this module renders, longer output is cut off with an ellipsis.
+    #[serde(default)]
+    module_max_width: Option<usize>,
+    /// Text that will be printed before this module, in the module's style. Nothing is printed if
+    /// the module renders nothing.
+    #[serde(default)]
+    prefix: Option<String>,
+    /// Text that will be printed after this module, in the module's style. Nothing is printed if
+    /// the module renders nothing.
+    #[serde(default)]
+    suffix: Option<String>,
+}
+
+fn default_placeholder_style() -> util::Style {
+    util::Style {
+        color: Some(util::Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl From<ModuleConfig> for Module {
+    fn from(module: ModuleConfig) -> Self {
+        Self {
+            module,
+            no_cache: false,
+            when: None,
+            placeholder: None,
+            placeholder_style: default_placeholder_style(),
+            module_max_width: None,
+            prefix: None,
+            suffix: None,
+        }
+    }
+}
+
+impl Module {
+    /// Whether the working copy is in the module's `when` revset, `true` if it has none.
+    fn is_shown(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+    ) -> Result<bool, CommandError> {
+        match &self.when {
+            Some(when) => state.working_copy_in(command_helper, when),
+            None => Ok(true),
+        }
+    }
 }
 
 /// A module that prints some info about the current jj repo.
@@ -191,22 +1029,258 @@ enum ModuleConfig {
     Commit(Commit),
     State(State),
     Metrics(Metrics),
+    Hint(Hint),
+    Heads(Heads),
+    FetchAge(FetchAge),
+    Upstream(Upstream),
+    OperationBadge(OperationBadge),
+    Path(Path),
+    FileCount(FileCount),
+    Conflicts(Conflicts),
+    Age(Age),
+    Author(Author),
+    Committer(Committer),
+    ChangeId(ChangeId),
+    CommitId(CommitId),
+    Ok(OkModule),
+    Locator(Locator),
+    LineBreak(LineBreak),
+}
+
+impl ModuleConfig {
+    /// The module's `type`, e.g. `Commit`.
+    fn kind(&self) -> &'static str {
+        match self {
+            ModuleConfig::Symbol(_) => "Symbol",
+            ModuleConfig::Bookmarks(_) => "Bookmarks",
+            ModuleConfig::Commit(_) => "Commit",
+            ModuleConfig::State(_) => "State",
+            ModuleConfig::Metrics(_) => "Metrics",
+            ModuleConfig::Hint(_) => "Hint",
+            ModuleConfig::Heads(_) => "Heads",
+            ModuleConfig::FetchAge(_) => "FetchAge",
+            ModuleConfig::Upstream(_) => "Upstream",
+            ModuleConfig::OperationBadge(_) => "OperationBadge",
+            ModuleConfig::Path(_) => "Path",
+            ModuleConfig::FileCount(_) => "FileCount",
+            ModuleConfig::Conflicts(_) => "Conflicts",
+            ModuleConfig::Age(_) => "Age",
+            ModuleConfig::Author(_) => "Author",
+            ModuleConfig::Committer(_) => "Committer",
+            ModuleConfig::ChangeId(_) => "ChangeId",
+            ModuleConfig::CommitId(_) => "CommitId",
+            ModuleConfig::Ok(_) => "Ok",
+            ModuleConfig::Locator(_) => "Locator",
+            ModuleConfig::LineBreak(_) => "LineBreak",
+        }
+    }
+
+    /// Starts loading expensive data in the background, so it's computed while the modules before
+    /// this one are parsed.
+    fn prefetch(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &crate::JJData,
+    ) -> Result<(), CommandError> {
+        match self {
+            ModuleConfig::Metrics(metrics) => metrics.prefetch(command_helper, state, data),
+            _ => Ok(()),
+        }
+    }
+
+    fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &GlobalConfig,
+    ) -> Result<(), CommandError> {
+        match self {
+            ModuleConfig::Symbol(symbol) => symbol.parse(command_helper, state, data, global),
+            ModuleConfig::Bookmarks(bookmarks) => {
+                bookmarks.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::Commit(commit) => commit.parse(command_helper, state, data, global),
+            ModuleConfig::State(warnings) => warnings.parse(command_helper, state, data, global),
+            ModuleConfig::Metrics(metrics) => metrics.parse(command_helper, state, data, global),
+            ModuleConfig::Hint(hint) => hint.parse(command_helper, state, data, global),
+            ModuleConfig::Heads(heads) => heads.parse(command_helper, state, data, global),
+            ModuleConfig::FetchAge(fetch_age) => {
+                fetch_age.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::Upstream(upstream) => upstream.parse(command_helper, state, data, global),
+            ModuleConfig::OperationBadge(badge) => badge.parse(command_helper, state, data, global),
+            ModuleConfig::Path(path) => path.parse(command_helper, state, data, global),
+            ModuleConfig::FileCount(file_count) => {
+                file_count.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::Conflicts(conflicts) => {
+                conflicts.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::Age(age) => age.parse(command_helper, state, data, global),
+            ModuleConfig::Author(author) => author.parse(command_helper, state, data, global),
+            ModuleConfig::Committer(committer) => {
+                committer.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::ChangeId(change_id) => {
+                change_id.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::CommitId(commit_id) => {
+                commit_id.parse(command_helper, state, data, global)
+            }
+            ModuleConfig::Ok(ok) => ok.parse(command_helper, state, data, global),
+            ModuleConfig::Locator(locator) => locator.parse(command_helper, state, data, global),
+            // Handled by the `ModuleWriter`.
+            ModuleConfig::LineBreak(_) => Ok(()),
+        }
+    }
+
+    fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        global: &GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        match self {
+            ModuleConfig::Symbol(symbol) => symbol.print(io, data, global, prev_style),
+            ModuleConfig::Bookmarks(bookmarks) => bookmarks.print(io, data, global, prev_style),
+            ModuleConfig::Commit(commit) => commit.print(io, data, global, prev_style),
+            ModuleConfig::State(warnings) => warnings.print(io, data, global, prev_style),
+            ModuleConfig::Metrics(metrics) => metrics.print(io, data, global, prev_style),
+            ModuleConfig::Hint(hint) => hint.print(io, data, global, prev_style),
+            ModuleConfig::Heads(heads) => heads.print(io, data, global, prev_style),
+            ModuleConfig::FetchAge(fetch_age) => fetch_age.print(io, data, global, prev_style),
+            ModuleConfig::Upstream(upstream) => upstream.print(io, data, global, prev_style),
+            ModuleConfig::OperationBadge(badge) => badge.print(io, data, global, prev_style),
+            ModuleConfig::Path(path) => path.print(io, data, global, prev_style),
+            ModuleConfig::FileCount(file_count) => file_count.print(io, data, global, prev_style),
+            ModuleConfig::Conflicts(conflicts) => conflicts.print(io, data, global, prev_style),
+            ModuleConfig::Age(age) => age.print(io, data, global, prev_style),
+            ModuleConfig::Author(author) => author.print(io, data, global, prev_style),
+            ModuleConfig::Committer(committer) => committer.print(io, data, global, prev_style),
+            ModuleConfig::ChangeId(change_id) => change_id.print(io, data, global, prev_style),
+            ModuleConfig::CommitId(commit_id) => commit_id.print(io, data, global, prev_style),
+            ModuleConfig::Ok(ok) => ok.print(io, data, global, prev_style),
+            ModuleConfig::Locator(locator) => locator.print(io, data, global, prev_style),
+            ModuleConfig::LineBreak(_) => Ok(()),
+        }
+    }
+
+    /// Drops any previously computed data this module renders so its `parse` recomputes it.
+    fn invalidate(&self, data: &mut crate::JJData) {
+        match self {
+            ModuleConfig::Bookmarks(_) => data.bookmarks = Default::default(),
+            ModuleConfig::Commit(_) => {
+                data.commit.desc = None;
+                data.commit.ahead = false;
+                data.commit.desc_source = None;
+                data.commit.commit_id = None;
+                data.commit.change_id = None;
+                data.commit.mine = None;
+            }
+            ModuleConfig::State(state) => state.invalidate(data),
+            ModuleConfig::Metrics(_) => data.commit.diff = None,
+            ModuleConfig::Heads(_) => data.heads = None,
+            ModuleConfig::FetchAge(_) => data.last_fetch = None,
+            ModuleConfig::Upstream(_) => data.upstream = None,
+            ModuleConfig::OperationBadge(_) => data.operation = None,
+            ModuleConfig::Path(_) => data.path = None,
+            ModuleConfig::FileCount(_) => data.file_count = None,
+            ModuleConfig::Conflicts(_) => data.conflicts = None,
+            ModuleConfig::Age(_) => data.commit.committed = None,
+            ModuleConfig::Author(_) | ModuleConfig::Committer(_) => {
+                data.commit.author = None;
+                data.commit.committer = None;
+            }
+            ModuleConfig::ChangeId(_) => data.commit.change_id = None,
+            ModuleConfig::CommitId(_) => data.commit.commit_id = None,
+            ModuleConfig::Ok(_) => data.commit.described = None,
+            ModuleConfig::Locator(_) => {
+                data.bookmarks = Default::default();
+                data.trunk_distance = None;
+            }
+            ModuleConfig::LineBreak(_) => {}
+            ModuleConfig::Symbol(symbol) => symbol.invalidate(data),
+            ModuleConfig::Hint(_) => {
+                data.commit.described = None;
+                hint::detector().invalidate(data);
+            }
+        }
+    }
+}
+
+impl Default for GlobalConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Default::default(),
+            timeout_text: None,
+            module_separator: default_separator(),
+            empty_separator: Default::default(),
+            trim_trailing_separator: Default::default(),
+            always_render: Default::default(),
+            hide_inherited_bookmark: Default::default(),
+            hide_empty_on_fresh_commit: Default::default(),
+            compact_below: Default::default(),
+            compact: Default::default(),
+            bookmarks: Default::default(),
+            reset_color: Default::default(),
+            catch_incompatible_repo: default_catch_incompatible_repo(),
+            incompatible_text: default_incompatible_text(),
+            outside_repo_text: None,
+            outside_repo_style: None,
+            notify_on: Default::default(),
+            focus_on: Default::default(),
+            escape: Default::default(),
+            powerline: Default::default(),
+            prompt_prefix: Default::default(),
+            prompt_prefix_style: Default::default(),
+            prompt_suffix: Default::default(),
+            prompt_suffix_style: Default::default(),
+            max_width: None,
+            snapshot_min_interval_ms: None,
+            cache: Default::default(),
+            on_module_error: Default::default(),
+            module_error_glyph: default_module_error_glyph(),
+            theme: None,
+            styles: Default::default(),
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            global: GlobalConfig {
-                timeout: Default::default(),
-                module_separator: default_separator(),
-                bookmarks: Default::default(),
-                reset_color: Default::default(),
-            },
+            include: Vec::new(),
+            global: Default::default(),
             modules: default_modules(),
         }
     }
 }
 
+/// Returns what `print` wrote, so tests can compare the rendered text.
+#[cfg(test)]
+pub(crate) fn rendered<E: std::fmt::Debug>(
+    print: impl FnOnce(&mut Vec<u8>) -> Result<(), E>,
+) -> String {
+    let mut out = Vec::new();
+    print(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// A tracked bookmark `distance` commits behind the working copy.
+#[cfg(test)]
+pub(crate) fn bookmark(name: &str, distance: usize) -> crate::Bookmark {
+    crate::Bookmark {
+        name: name.to_string(),
+        distance,
+        kind: crate::BookmarkKind::Tracked,
+        ahead: false,
+        target: jj_lib::backend::CommitId::from_hex("abc123def456"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,6 +1303,539 @@ mod tests {
         assert_debug_snapshot!(c);
     }
 
+    #[test]
+    fn default_config_roundtrips_through_config_builder() {
+        let c = ::config::Config::builder()
+            .add_source(::config::Config::try_from(&Config::default()).unwrap())
+            .build()
+            .unwrap();
+        let c: Config = c.try_deserialize().unwrap();
+
+        assert_eq!(c.modules.len(), default_modules().len());
+    }
+
+    fn write_modules(writer: &mut ModuleWriter, modules: &[&str]) -> String {
+        rendered(|io| {
+            for module in modules {
+                writer.write(io, module.as_bytes())?;
+            }
+            writer.finish(io)
+        })
+    }
+
+    #[test]
+    fn separators_skip_empty_modules() {
+        let mut writer = ModuleWriter::new(" ", None, false);
+        assert_eq!(write_modules(&mut writer, &["a", "", "b"]), "a b ");
+    }
+
+    #[test]
+    fn trim_trailing_separator() {
+        let mut writer = ModuleWriter::new(" ", Some(" · "), true);
+        assert_eq!(write_modules(&mut writer, &["a", "b", ""]), "a b");
+    }
+
+    #[test]
+    fn prompt_prefix_only_around_output() {
+        let c: Config = toml::from_str(
+            r#"
+reset_color = false
+prompt_prefix = "on "
+prompt_suffix = "!"
+
+[prompt_prefix_style]
+color = "White"
+
+[[module]]
+type = "Heads"
+"#,
+        )
+        .unwrap();
+        let render = |heads| {
+            let data = crate::JJData {
+                heads: Some(heads),
+                ..Default::default()
+            };
+            rendered(|io| c.print_data(&data, io))
+        };
+
+        assert_eq!(
+            render(2),
+            "\u{1b}[0m\u{1b}[37mon \u{1b}[0m\u{1b}[0m\u{1b}[m\u{1b}[0m\u{1b}[33m2 !"
+        );
+        assert_eq!(render(1), "");
+    }
+
+    #[test]
+    fn focus_module_must_be_a_module_type() {
+        let parse = |module: &str| {
+            toml::from_str::<Config>(&format!(
+                "[[focus_on]]\nwhen = \"Conflict\"\nmodule = \"{module}\"\n"
+            ))
+        };
+
+        assert!(parse("State").is_ok());
+        let err = parse("Stat").unwrap_err().to_string();
+        assert!(err.contains("unknown variant `Stat`"), "{err}");
+    }
+
+    #[test]
+    fn kind_matches_type() {
+        for kind in [
+            "Symbol",
+            "Bookmarks",
+            "Commit",
+            "State",
+            "Metrics",
+            "Hint",
+            "Heads",
+            "FetchAge",
+            "Upstream",
+            "OperationBadge",
+            "Path",
+            "FileCount",
+            "Conflicts",
+            "Age",
+            "Author",
+            "Committer",
+            "ChangeId",
+            "CommitId",
+            "Ok",
+            "Locator",
+            "LineBreak",
+        ] {
+            let module: ModuleConfig = toml::from_str(&format!("type = \"{kind}\"")).unwrap();
+            assert_eq!(module.kind(), kind);
+        }
+    }
+
+    #[test]
+    fn focus_renders_only_focused_module() {
+        let c: Config = toml::from_str(
+            r#"
+reset_color = false
+
+[[focus_on]]
+when = "Conflict"
+module = "State"
+
+[[focus_on]]
+when = "Divergent"
+text = "DIVERGED"
+
+[[module]]
+type = "Heads"
+
+[[module]]
+type = "State"
+"#,
+        )
+        .unwrap();
+        let render = |conflict, divergent| {
+            let data = crate::JJData {
+                heads: Some(2),
+                commit: crate::CommitData {
+                    warnings: crate::CommitWarnings {
+                        conflict: Some(conflict),
+                        divergent: Some(divergent),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            rendered(|io| c.print_data(&data, io))
+        };
+
+        assert_eq!(
+            render(true, true),
+            "\u{1b}[0m\u{1b}[31m(CONFLICT)\u{1b}[0m\u{1b}[0m\u{1b}[m \u{1b}[36m(DIVERGENT) "
+        );
+        assert_eq!(render(false, true), "\u{1b}[0m\u{1b}[31mDIVERGED ");
+        assert_eq!(render(false, false), "\u{1b}[0m\u{1b}[33m2 ");
+    }
+
+    #[test]
+    fn powerline_segments() {
+        let c: Config = toml::from_str(
+            r#"
+reset_color = false
+
+[powerline]
+palette = ["Blue", "Green"]
+
+[[module]]
+type = "Symbol"
+
+[[module]]
+type = "Heads"
+
+[[module]]
+type = "Commit"
+"#,
+        )
+        .unwrap();
+        let data = crate::JJData {
+            heads: Some(2),
+            commit: crate::CommitData {
+                desc: Some("wip".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let out = rendered(|io| c.print_data(&data, io));
+        assert_debug_snapshot!(out);
+    }
+
+    #[test]
+    fn bookmark_sort_orders() {
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(
+                    [("feature", 0), ("main", 3), ("release", 1), ("docs", 3)]
+                        .into_iter()
+                        .map(|(name, distance)| crate::Bookmark {
+                            name: name.to_string(),
+                            distance,
+                            kind: crate::BookmarkKind::Tracked,
+                            ahead: false,
+                            target: jj_lib::backend::CommitId::from_hex("abc123"),
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        for sort_by in ["Distance", "Name", "NameDescending"] {
+            let c: Config = toml::from_str(&format!(
+                r#"
+reset_color = false
+
+[[module]]
+type = "Bookmarks"
+sort_by = "{sort_by}"
+max_bookmarks = 3
+"#
+            ))
+            .unwrap();
+            let out = rendered(|io| c.print_data(&data, io));
+            assert_debug_snapshot!(format!("bookmark_sort_{sort_by}"), out);
+        }
+    }
+
+    #[test]
+    fn empty_separator_marks_skipped_module() {
+        let mut writer = ModuleWriter::new(" ", Some(" · "), false);
+        assert_eq!(write_modules(&mut writer, &["a", "", "b", "c"]), "a · b c ");
+    }
+
+    #[test]
+    fn no_cache_only_invalidates_its_own_data() {
+        let c: Config = toml::from_str(
+            r#"
+[[module]]
+type = "Bookmarks"
+
+[[module]]
+type = "Metrics"
+no_cache = true
+"#,
+        )
+        .unwrap();
+
+        let mut data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(Vec::new()),
+                ..Default::default()
+            },
+            commit: crate::CommitData {
+                diff: Some(Default::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for module in c.modules.iter().filter(|module| module.no_cache) {
+            module.module.invalidate(&mut data);
+        }
+
+        assert!(data.bookmarks.bookmarks.is_some());
+        assert!(data.commit.diff.is_none());
+    }
+
+    #[test]
+    fn placeholders_render_without_working_copy() {
+        let c: Config = toml::from_str(
+            r#"
+always_render = true
+
+[[module]]
+type = "Commit"
+placeholder = "no commit"
+
+[[module]]
+type = "Metrics"
+placeholder = "-"
+"#,
+        )
+        .unwrap();
+
+        let data = crate::JJData::default();
+        let rendered: Vec<String> = c
+            .modules
+            .iter()
+            .map(|module| rendered(|io| c.print_module(module, &data, io, &mut None)))
+            .collect();
+
+        assert_eq!(
+            rendered,
+            ["\u{1b}[0m\u{1b}[90mno commit", "\u{1b}[0m\u{1b}[90m-"]
+        );
+    }
+
+    #[test]
+    fn module_max_width_truncates_whole_module() {
+        let c: Config = toml::from_str(
+            r#"
+[[module]]
+type = "Bookmarks"
+max_bookmarks = 3
+module_max_width = 12
+"#,
+        )
+        .unwrap();
+
+        let bookmark = |name: &str, distance| crate::Bookmark {
+            name: name.to_string(),
+            distance,
+            kind: crate::BookmarkKind::Tracked,
+            ahead: false,
+            target: jj_lib::backend::CommitId::from_hex("00"),
+        };
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(vec![
+                    bookmark("main", 0),
+                    bookmark("feature", 0),
+                    bookmark("release", 2),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let out = rendered(|io| c.print_module(&c.modules[0], &data, io, &mut None));
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35mmain featur…");
+    }
+
+    #[test]
+    fn max_width_truncates_across_modules() {
+        let c: Config = toml::from_str(
+            r#"
+reset_color = false
+max_width = 8
+
+[[module]]
+type = "Bookmarks"
+
+[[module]]
+type = "Commit"
+"#,
+        )
+        .unwrap();
+        let render = |desc: &str| {
+            let data = crate::JJData {
+                bookmarks: crate::BookmarkData {
+                    bookmarks: Some(vec![crate::Bookmark {
+                        name: "main".to_string(),
+                        distance: 0,
+                        kind: crate::BookmarkKind::Tracked,
+                        ahead: false,
+                        target: jj_lib::backend::CommitId::from_hex("00"),
+                    }]),
+                    ..Default::default()
+                },
+                commit: crate::CommitData {
+                    desc: Some(desc.to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            rendered(|io| c.print_data(&data, io))
+        };
+
+        assert_eq!(
+            render("fix parser"),
+            "\u{1b}[0m\u{1b}[35mmain \u{1b}[0m\u{1b}[0m\u{1b}[mfi…"
+        );
+        assert_eq!(
+            render("fi"),
+            "\u{1b}[0m\u{1b}[35mmain \u{1b}[0m\u{1b}[0m\u{1b}[mfi "
+        );
+    }
+
+    #[test]
+    fn root_commit_renders_root_text_only() {
+        let c: Config = toml::from_str(
+            r#"
+reset_color = false
+
+[[module]]
+type = "Commit"
+root_text = "(root)"
+
+[[module]]
+type = "State"
+
+[[module]]
+type = "Metrics"
+"#,
+        )
+        .unwrap();
+        let render = |root| {
+            let data = crate::JJData {
+                commit: crate::CommitData {
+                    desc: Some(String::new()),
+                    root: Some(root),
+                    warnings: crate::CommitWarnings {
+                        empty: Some(true),
+                        ..Default::default()
+                    },
+                    diff: Some(Default::default()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let out = rendered(|io| c.print_data(&data, io));
+            util::strip_ansi(&out)
+        };
+
+        assert_eq!(render(true), "(root) ");
+        assert_eq!(render(false), "󰆇 (EMPTY) [0 +0-0] ");
+    }
+
+    #[test]
+    fn timeout_fallback_honors_color() {
+        let render = |text, color| rendered(|io| write_timeout_fallback(io, text, color));
+        assert_eq!(render(None, true), "\u{1b}[0m\u{1b}[m ");
+        assert_eq!(render(None, false), " ");
+        assert_eq!(render(Some("⏳"), true), "\u{1b}[0m\u{1b}[m⏳");
+        assert_eq!(render(Some("⏳"), false), "⏳");
+    }
+
+    #[test]
+    fn line_break_drops_separator_and_resets_style() {
+        let c: Config = toml::from_str(
+            r#"
+module_separator = " | "
+reset_color = false
+
+[[module]]
+type = "Commit"
+color = "Red"
+
+[[module]]
+type = "Heads"
+
+[[module]]
+type = "LineBreak"
+
+[[module]]
+type = "Commit"
+color = "Green"
+"#,
+        )
+        .unwrap();
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                desc: Some("wip".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let out = rendered(|io| c.print_data(&data, io));
+
+        assert_eq!(
+            out,
+            "\u{1b}[0m\u{1b}[31mwip\u{1b}[0m\u{1b}[0m\u{1b}[m\n\u{1b}[0m\u{1b}[32mwip | "
+        );
+    }
+
+    #[test]
+    fn prefix_and_suffix_wrap_rendered_modules() {
+        let c: Config = toml::from_str(
+            r#"
+[[module]]
+type = "Bookmarks"
+prefix = "["
+suffix = "]"
+"#,
+        )
+        .unwrap();
+        let render = |bookmarks| {
+            let data = crate::JJData {
+                bookmarks: crate::BookmarkData {
+                    bookmarks: Some(bookmarks),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            rendered(|io| c.print_module(&c.modules[0], &data, io, &mut None))
+        };
+
+        assert_eq!(
+            render(vec![crate::Bookmark {
+                name: "main".to_string(),
+                distance: 0,
+                kind: crate::BookmarkKind::Tracked,
+                ahead: false,
+                target: jj_lib::backend::CommitId::from_hex("00"),
+            }]),
+            "\u{1b}[0m\u{1b}[35m[main]"
+        );
+        assert_eq!(render(Vec::new()), "");
+    }
+
+    #[test]
+    fn compact_overrides_apply_below_threshold() {
+        let config = r#"
+compact_below = 60
+
+[compact.Commit]
+max_length = 12
+
+[compact.Bookmarks]
+max_bookmarks = 1
+untracked = { bold = true }
+
+[[module]]
+type = "Bookmarks"
+max_bookmarks = 3
+[module.untracked]
+color = "Yellow"
+
+[[module]]
+type = "Commit"
+max_length = 40
+"#;
+        let module = |c: &Config, i: usize| toml::Value::try_from(&c.modules[i]).unwrap();
+
+        let mut wide: Config = toml::from_str(config).unwrap();
+        wide.apply_compact(100).unwrap();
+        assert_eq!(module(&wide, 0)["max_bookmarks"].as_integer(), Some(3));
+        assert_eq!(module(&wide, 1)["max_length"].as_integer(), Some(40));
+
+        let mut narrow: Config = toml::from_str(config).unwrap();
+        narrow.apply_compact(40).unwrap();
+        let bookmarks = module(&narrow, 0);
+        assert_eq!(bookmarks["max_bookmarks"].as_integer(), Some(1));
+        assert_eq!(bookmarks["untracked"]["color"].as_str(), Some("Yellow"));
+        assert_eq!(bookmarks["untracked"]["bold"].as_bool(), Some(true));
+        assert_eq!(module(&narrow, 1)["max_length"].as_integer(), Some(12));
+    }
+
     #[test]
     fn parse_minimal_config1() {
         let minimal = r#""#;
@@ -0,0 +1,146 @@
+use std::{io::Write, time::SystemTime};
+
+use jj_cli::command_error::CommandError;
+use jj_lib::backend::MillisSinceEpoch;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints how long ago the working copy commit was last committed, e.g. `3m`, `2h` or `4d`.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Age {
+    /// Text that will be printed before the elapsed time.
+    #[serde(default)]
+    symbol: String,
+    /// Do not render anything if the commit is younger than this many seconds.
+    #[serde(default)]
+    threshold_seconds: Option<u64>,
+    /// Controls how the elapsed time is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl Default for Age {
+    fn default() -> Self {
+        Self {
+            symbol: String::new(),
+            threshold_seconds: None,
+            style: default_style(),
+        }
+    }
+}
+
+impl Age {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        self.print_at(io, data, MillisSinceEpoch(now), prev_style)
+    }
+
+    fn print_at(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        now: MillisSinceEpoch,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(Some(committed)) = data.commit.committed else {
+            return Ok(());
+        };
+
+        let elapsed = (now.0 - committed.0).max(0) as u64 / 1000;
+        if self
+            .threshold_seconds
+            .is_some_and(|threshold| elapsed < threshold)
+        {
+            return Ok(());
+        }
+        self.style.print(io, default_style(), prev_style)?;
+        write!(
+            io,
+            "{}{}",
+            self.symbol,
+            super::fetch_age::format_elapsed(elapsed)
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.commit.committed.is_none() {
+            data.commit.committed = Some(
+                state
+                    .commit(command_helper)?
+                    .as_ref()
+                    .map(|commit| commit.committer().timestamp.timestamp),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    fn render(config: &Age, elapsed_secs: i64) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                committed: Some(Some(MillisSinceEpoch(0))),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print_at(io, &data, MillisSinceEpoch(elapsed_secs * 1000), &mut None)
+        })
+    }
+
+    #[test]
+    fn formatting_rules() {
+        let config = Age {
+            style: Style::default(),
+            ..Default::default()
+        };
+        let rendered: Vec<String> = [0, 59, 60, 3599, 3600, 86399, 86400, 30 * 86400, -5]
+            .into_iter()
+            .map(|secs| format!("{secs}: {:?}", render(&config, secs)))
+            .collect();
+        assert_snapshot!(rendered.join("\n"));
+    }
+
+    #[test]
+    fn threshold_hides_young_commits() {
+        let config = Age {
+            threshold_seconds: Some(3600),
+            ..Default::default()
+        };
+        assert_eq!(render(&config, 3599), "");
+        assert_eq!(render(&config, 3600), "\u{1b}[0m\u{1b}[90m1h");
+    }
+}
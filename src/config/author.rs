@@ -0,0 +1,211 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the author of the working copy commit.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Author {
+    /// Which part of the author signature is rendered.
+    #[serde(default)]
+    show: Show,
+    /// Maximum length the author will be truncated to.
+    #[serde(default)]
+    max_length: Option<usize>,
+    /// Text that will be printed after the author when the committer is someone else.
+    #[serde(default)]
+    mismatch_symbol: Option<String>,
+    /// Controls how the author is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Show {
+    /// Email => Render the email
+    Email,
+    /// Name => [default] Render the name
+    #[default]
+    Name,
+    /// Both => Render `name <email>`
+    Both,
+}
+
+impl Show {
+    fn format(self, signature: &crate::CommitSignature) -> String {
+        match self {
+            Show::Email => signature.email.clone(),
+            Show::Name => signature.name.clone(),
+            Show::Both => format!("{} <{}>", signature.name, signature.email),
+        }
+    }
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Yellow),
+        ..Default::default()
+    }
+}
+
+impl Default for Author {
+    fn default() -> Self {
+        Self {
+            show: Default::default(),
+            max_length: None,
+            mismatch_symbol: None,
+            style: default_style(),
+        }
+    }
+}
+
+impl Author {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        print_signature(
+            io,
+            data.commit.author.as_ref(),
+            data,
+            self.show,
+            self.max_length,
+            self.mismatch_symbol.as_deref(),
+            &self.style,
+            default_style(),
+            global,
+            prev_style,
+        )
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        resolve_signatures(command_helper, state, data)
+    }
+}
+
+/// Prints `signature` (the author or the committer), followed by `mismatch_symbol` if author and
+/// committer differ. Prints nothing without a working copy commit.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn print_signature(
+    io: &mut impl Write,
+    signature: Option<&crate::CommitSignature>,
+    data: &crate::JJData,
+    show: Show,
+    max_length: Option<usize>,
+    mismatch_symbol: Option<&str>,
+    style: &Style,
+    default_style: Style,
+    global: &super::GlobalConfig,
+    prev_style: &mut Option<nu_ansi_term::Style>,
+) -> Result<(), CommandError> {
+    let Some(signature) = signature else {
+        return Ok(());
+    };
+
+    style.print(io, default_style, prev_style)?;
+    crate::print_ansi_truncated(
+        max_length,
+        io,
+        &show.format(signature),
+        false,
+        global.escape,
+    )?;
+    if let Some(mismatch_symbol) = mismatch_symbol
+        && data.commit.author != data.commit.committer
+    {
+        write!(io, "{mismatch_symbol}")?;
+    }
+    Ok(())
+}
+
+/// Loads the author and committer of the working copy commit.
+pub(super) fn resolve_signatures(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+) -> Result<(), CommandError> {
+    if data.commit.author.is_some() && data.commit.committer.is_some() {
+        return Ok(());
+    }
+    let Some(commit) = state.commit(command_helper)? else {
+        return Ok(());
+    };
+    let signature = |signature: &jj_lib::backend::Signature| crate::CommitSignature {
+        name: signature.name.clone(),
+        email: signature.email.clone(),
+    };
+    data.commit.author = Some(signature(commit.author()));
+    data.commit.committer = Some(signature(commit.committer()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &Author, author: Option<crate::CommitSignature>) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                author,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn renders_selected_parts() {
+        let author = || {
+            Some(crate::CommitSignature {
+                name: "Jane Doe".to_string(),
+                email: "jane@example.com".to_string(),
+            })
+        };
+        let config = |show, max_length| Author {
+            show,
+            max_length,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&config(Show::Name, None), author()),
+            "\u{1b}[0m\u{1b}[33mJane Doe"
+        );
+        assert_eq!(
+            render(&config(Show::Email, None), author()),
+            "\u{1b}[0m\u{1b}[33mjane@example.com"
+        );
+        assert_eq!(
+            render(&config(Show::Both, Some(12)), author()),
+            "\u{1b}[0m\u{1b}[33mJane Doe <j…"
+        );
+    }
+
+    #[test]
+    fn renders_nothing_without_commit() {
+        assert_eq!(render(&Author::default(), None), "");
+    }
+}
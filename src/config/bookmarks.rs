@@ -1,9 +1,14 @@
-use std::io::Write;
+use std::{borrow::Cow, collections::HashMap, io::Write};
 
 use jj_cli::command_error::CommandError;
+use jj_lib::{
+    backend::CommitId, git::REMOTE_NAME_FOR_LOCAL_GIT_REPO, id_prefix::IdPrefixIndex,
+    object_id::ObjectId as _, repo::Repo as _,
+};
 #[cfg(feature = "json-schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr as _;
 
 use super::util::{Color, Style};
 
@@ -20,20 +25,98 @@ pub struct Bookmarks {
     /// Controls how untracked remote bookmarks are rendered.
     #[serde(default = "default_untracked_style")]
     untracked: Style,
+    /// Controls how remote bookmarks (`name@remote`) are rendered, falling back to the style of
+    /// tracked or untracked bookmarks for anything it doesn't set.
+    remote_style: Option<Style>,
     /// A suffix that will be printed when the given bookmark is behind the working copy.
     #[serde(default = "default_behind_symbol")]
     behind_symbol: Option<char>,
+    /// A suffix that will be printed instead of the `behind_symbol` when the given bookmark is
+    /// on a descendant of the working copy.
+    #[serde(default = "default_ahead_symbol")]
+    ahead_symbol: Option<char>,
     /// Maximum amount of bookmarks that will be rendered.
     #[serde(default = "default_max_bookmarks")]
     max_bookmarks: Option<usize>,
     /// Maximum length the bookmark name will be truncated to.
     max_length: Option<usize>,
+    /// Shorten slash-delimited names like `feature/backend/oauth-refresh` by abbreviating the
+    /// segments before the last one (`f/b/oauth-refresh`), or replacing them with `…`
+    /// (`…/oauth-refresh`), instead of cutting off the end. The last segment is always kept whole.
+    #[serde(default)]
+    keep_last_segment: bool,
+    /// Maximum width of all rendered bookmarks including separators. Bookmarks that would exceed
+    /// it are replaced by the `overflow_symbol`.
+    max_total_length: Option<usize>,
+    /// Text that replaces the bookmarks that were cut off by `max_total_length`.
+    #[serde(default = "default_overflow_symbol")]
+    overflow_symbol: String,
     /// Do not render quotes around bookmark names.
     #[serde(default = "default_surround_with_quotes")]
     surround_with_quotes: bool,
+    /// When quotes are off and a bookmark name contains the `separator`, escape the separator with
+    /// a backslash instead of quoting the name.
+    #[serde(default)]
+    escape_separator: bool,
     /// Ignore Commits without a description.
     #[serde(default = "default_ignore_empty_commits")]
     ignore_empty_commits: IgnoreEmpty,
+    /// Append the shortest unique commit id of the bookmark's target after the
+    /// `target_id_separator`, e.g. `main:abc1`.
+    #[serde(default)]
+    show_target_id: bool,
+    /// Text between a bookmark and its target id, distinct from the `@` of remote bookmarks.
+    #[serde(default = "default_target_id_separator")]
+    target_id_separator: String,
+    /// Controls how the target commit id is rendered.
+    #[serde(default = "default_target_id_style")]
+    target_id: Style,
+    /// Skip the bookmarks on `@` itself and show the nearest bookmark further up instead.
+    #[serde(default)]
+    hide_when_current: bool,
+    /// Controls what the number after the `behind_symbol` counts.
+    #[serde(default)]
+    behind_mode: BehindMode,
+    /// Controls the order bookmarks are rendered in, `max_bookmarks` is applied afterwards.
+    #[serde(default)]
+    sort_by: SortBy,
+    /// Append a marker to local bookmarks showing whether they track a remote bookmark.
+    #[serde(default)]
+    show_tracked_marker: Option<TrackedMarker>,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TrackedMarker {
+    /// Text that will be printed after a local bookmark tracking a remote bookmark.
+    tracked: String,
+    /// Text that will be printed after a local bookmark not tracking any remote bookmark.
+    untracked: String,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum SortBy {
+    /// Distance => [default] Closest to the working copy first
+    #[default]
+    Distance,
+    /// Name => Alphabetically, regardless of the distance
+    Name,
+    /// NameDescending => Reverse alphabetically, regardless of the distance
+    NameDescending,
+    /// MostRecent => Most recently committed target first, regardless of the distance
+    MostRecent,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum BehindMode {
+    /// ToHead => [default] The number of commits between the bookmark and the working copy
+    #[default]
+    ToHead,
+    /// ToNextBookmark => The number of commits between the bookmark and the next rendered bookmark
+    /// closer to the working copy, showing the gaps within a stack
+    ToNextBookmark,
 }
 
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
@@ -65,10 +148,28 @@ fn default_untracked_style() -> Style {
     }
 }
 
+fn default_target_id_style() -> Style {
+    let mut style = Style::default();
+    style.attributes.dimmed = Some(true);
+    style
+}
+
+fn default_target_id_separator() -> String {
+    ":".to_string()
+}
+
 fn default_behind_symbol() -> Option<char> {
     Some('⇡')
 }
 
+fn default_ahead_symbol() -> Option<char> {
+    Some('⇣')
+}
+
+fn default_overflow_symbol() -> String {
+    "…".to_string()
+}
+
 fn default_separator() -> String {
     " ".to_string()
 }
@@ -86,12 +187,25 @@ impl Default for Bookmarks {
         Self {
             style: default_style(),
             untracked: default_untracked_style(),
+            remote_style: None,
             behind_symbol: default_behind_symbol(),
+            ahead_symbol: default_ahead_symbol(),
             max_bookmarks: default_max_bookmarks(),
             separator: default_separator(),
             max_length: Default::default(),
+            keep_last_segment: false,
+            max_total_length: None,
+            overflow_symbol: default_overflow_symbol(),
             surround_with_quotes: false,
+            escape_separator: false,
             ignore_empty_commits: default_ignore_empty_commits(),
+            show_target_id: false,
+            target_id_separator: default_target_id_separator(),
+            target_id: default_target_id_style(),
+            hide_when_current: false,
+            behind_mode: Default::default(),
+            sort_by: Default::default(),
+            show_tracked_marker: None,
         }
     }
 }
@@ -101,47 +215,163 @@ impl Bookmarks {
         &self,
         io: &mut impl Write,
         data: &crate::JJData,
-        module_separator: &str,
+        global: &super::GlobalConfig,
         prev_style: &mut Option<nu_ansi_term::Style>,
     ) -> Result<(), CommandError> {
-        let Some(bookmarks) = data.bookmarks.bookmarks.as_ref() else {
-            unreachable!()
+        let Some(bookmarks) = self.found(data) else {
+            return Ok(());
         };
 
-        if self.max_bookmarks == Some(0) {
+        let max_bookmarks = self.max_bookmarks.unwrap_or(usize::MAX);
+        if max_bookmarks == 0 || bookmarks.is_empty() {
             return Ok(());
         }
 
-        let Some(bookmark) = bookmarks.first() else {
-            return Ok(());
+        let inherited = data
+            .commit
+            .desc_source
+            .as_ref()
+            .filter(|_| global.hide_inherited_bookmark);
+        let mut displayed: Vec<&crate::Bookmark> = bookmarks
+            .iter()
+            .filter(|bookmark| Some(&bookmark.target) != inherited)
+            .collect();
+        sort_bookmarks(
+            self.sort_by,
+            &data.bookmarks.target_timestamps,
+            &mut displayed,
+        );
+        displayed.truncate(max_bookmarks);
+        let behind = behind_counts(self.behind_mode, &displayed);
+
+        let mut width = 0;
+        for (i, (bookmark, behind)) in displayed.into_iter().zip(behind).enumerate() {
+            let Some(max_total_length) = self.max_total_length else {
+                if i != 0 {
+                    write!(io, "{}", self.separator)?;
+                }
+                self.print_bookmark(io, data, global, bookmark, behind, prev_style)?;
+                continue;
+            };
+
+            // Render into a buffer first to measure the entry, dropping it if it doesn't fit.
+            let style_before = *prev_style;
+            let mut entry = Vec::new();
+            if i != 0 {
+                write!(entry, "{}", self.separator)?;
+            }
+            self.print_bookmark(&mut entry, data, global, bookmark, behind, prev_style)?;
+            width += super::util::visible_width(&String::from_utf8_lossy(&entry));
+            if width > max_total_length {
+                *prev_style = style_before;
+                if i != 0 {
+                    write!(io, "{}", self.separator)?;
+                }
+                write!(io, "{}", self.overflow_symbol)?;
+                break;
+            }
+            io.write_all(&entry)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_bookmark(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        global: &super::GlobalConfig,
+        bookmark: &crate::Bookmark,
+        behind: usize,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let (style, fallback) = match bookmark.kind {
+            crate::BookmarkKind::Tracked => (&self.style, default_style()),
+            crate::BookmarkKind::Untracked => (&self.untracked, default_untracked_style()),
         };
+        let style = match &self.remote_style {
+            Some(remote_style) if bookmark.name.contains('@') => {
+                remote_style.merge_with_fallback(Some(style.merge_with_fallback(Some(fallback))))
+            }
+            _ => style.merge_with_fallback(Some(fallback)),
+        };
+        style.print(io, None, prev_style)?;
 
-        match bookmark.kind {
-            crate::BookmarkKind::Tracked => self.style.print(io, default_style(), prev_style)?,
-            crate::BookmarkKind::Untracked => {
-                self.untracked
-                    .print(io, default_untracked_style(), prev_style)?;
+        match self.max_length {
+            Some(max_length) if self.keep_last_segment && bookmark.name.contains('/') => {
+                let shortened = shorten_segments(&bookmark.name, max_length);
+                let (name, quoted) = self.disambiguate(&shortened);
+                crate::print_ansi_truncated(None, io, &name, quoted, global.escape)?;
+            }
+            max_length => {
+                let (name, quoted) = self.disambiguate(&bookmark.name);
+                crate::print_ansi_truncated(max_length, io, &name, quoted, global.escape)?;
             }
         }
 
-        crate::print_ansi_truncated(
-            self.max_length,
-            io,
-            &bookmark.name,
-            self.surround_with_quotes,
-        )?;
+        if self.show_target_id
+            && let Some(len) = data.bookmarks.target_prefix_lens.get(&bookmark.target)
+        {
+            self.target_id
+                .print(io, default_target_id_style(), prev_style)?;
+            let hex = bookmark.target.hex();
+            write!(
+                io,
+                "{}{}",
+                self.target_id_separator,
+                &hex[..(*len).min(hex.len())]
+            )?;
+            style.print(io, None, prev_style)?;
+        }
+
+        if let Some(marker) = &self.show_tracked_marker
+            && let Some(tracking) = data.bookmarks.tracking.get(&bookmark.name)
+        {
+            let marker = if *tracking {
+                &marker.tracked
+            } else {
+                &marker.untracked
+            };
+            write!(io, "{marker}")?;
+        }
 
         if bookmark.distance != 0 {
-            match self.behind_symbol {
-                Some(s) => write!(io, "{s}{}", bookmark.distance)?,
-                None => write!(io, "{}", bookmark.distance)?,
+            let symbol = if bookmark.ahead {
+                self.ahead_symbol
+            } else {
+                self.behind_symbol
+            };
+            match symbol {
+                Some(s) => write!(io, "{s}{behind}")?,
+                None => write!(io, "{behind}")?,
             }
         }
-        write!(io, "{module_separator}")?;
-
         Ok(())
     }
 
+    /// The name to render and whether to quote it, so names containing the `separator` can't be
+    /// mistaken for several bookmarks.
+    fn disambiguate<'a>(&self, name: &'a str) -> (Cow<'a, str>, bool) {
+        if self.surround_with_quotes || self.separator.is_empty() || !name.contains(&self.separator)
+        {
+            return (Cow::Borrowed(name), self.surround_with_quotes);
+        }
+        if self.escape_separator {
+            let escaped = format!("\\{}", self.separator);
+            (Cow::Owned(name.replace(&self.separator, &escaped)), false)
+        } else {
+            (Cow::Borrowed(name), true)
+        }
+    }
+
+    /// The bookmarks this module renders, `None` until they were searched.
+    fn found<'a>(&self, data: &'a crate::JJData) -> Option<&'a Vec<crate::Bookmark>> {
+        match self.hide_when_current {
+            true => data.bookmarks.above_working_copy.as_ref(),
+            false => data.bookmarks.bookmarks.as_ref(),
+        }
+    }
+
     pub(crate) fn parse(
         &self,
         command_helper: &jj_cli::cli_util::CommandHelper,
@@ -149,20 +379,441 @@ impl Bookmarks {
         data: &mut crate::JJData,
         global: &super::GlobalConfig,
     ) -> Result<(), CommandError> {
-        if data.bookmarks.bookmarks.is_some() {
-            return Ok(());
+        let found = match self.hide_when_current {
+            true => &mut data.bookmarks.above_working_copy,
+            false => &mut data.bookmarks.bookmarks,
+        };
+        if found.is_none() {
+            let workspace_helper = state.workspace_helper(command_helper)?;
+            let view = workspace_helper.repo().view();
+
+            let mut bookmarks = Vec::new();
+
+            crate::find_parent_bookmarks(
+                workspace_helper,
+                view,
+                &global.bookmarks,
+                self.hide_when_current,
+                &mut bookmarks,
+            )?;
+
+            *found = Some(bookmarks);
+        }
+
+        if self.show_target_id {
+            let repo = state.repo(command_helper)?;
+            let bookmarks = self.found(data).cloned().unwrap_or_default();
+            for bookmark in bookmarks {
+                if data
+                    .bookmarks
+                    .target_prefix_lens
+                    .contains_key(&bookmark.target)
+                {
+                    continue;
+                }
+                let len = IdPrefixIndex::empty()
+                    .shortest_commit_prefix_len(repo.as_ref(), &bookmark.target)?;
+                data.bookmarks
+                    .target_prefix_lens
+                    .insert(bookmark.target.clone(), len);
+            }
+        }
+
+        if self.show_tracked_marker.is_some() {
+            let repo = state.repo(command_helper)?;
+            let view = repo.view();
+            for bookmark in &self.found(data).cloned().unwrap_or_default() {
+                if data.bookmarks.tracking.contains_key(&bookmark.name)
+                    || view
+                        .local_bookmarks()
+                        .all(|(name, _)| name.as_str() != bookmark.name)
+                {
+                    continue;
+                }
+                let tracking = view.all_remote_bookmarks().any(|(symbol, remote_ref)| {
+                    symbol.name.as_str() == bookmark.name
+                        && symbol.remote != REMOTE_NAME_FOR_LOCAL_GIT_REPO
+                        && remote_ref.is_tracked()
+                });
+                data.bookmarks
+                    .tracking
+                    .insert(bookmark.name.clone(), tracking);
+            }
+        }
+
+        if self.sort_by == SortBy::MostRecent {
+            let repo = state.repo(command_helper)?;
+            for bookmark in &self.found(data).cloned().unwrap_or_default() {
+                if data
+                    .bookmarks
+                    .target_timestamps
+                    .contains_key(&bookmark.target)
+                {
+                    continue;
+                }
+                let commit = repo.store().get_commit(&bookmark.target)?;
+                data.bookmarks.target_timestamps.insert(
+                    bookmark.target.clone(),
+                    commit.committer().timestamp.timestamp.0,
+                );
+            }
         }
+        Ok(())
+    }
+}
 
-        let workspace_helper = state.workspace_helper(command_helper)?;
-        let view = workspace_helper.repo().view();
+/// Orders the bookmarks to render. With the default order, bookmarks on the working copy itself
+/// come first so they survive the `max_bookmarks` cap.
+fn sort_bookmarks(
+    sort_by: SortBy,
+    timestamps: &HashMap<CommitId, i64>,
+    bookmarks: &mut [&crate::Bookmark],
+) {
+    match sort_by {
+        SortBy::Distance => bookmarks.sort_by_key(|bookmark| bookmark.distance),
+        SortBy::Name => bookmarks.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortBy::NameDescending => bookmarks.sort_by(|a, b| b.name.cmp(&a.name)),
+        SortBy::MostRecent => bookmarks
+            .sort_by_key(|bookmark| std::cmp::Reverse(timestamps.get(&bookmark.target).copied())),
+    }
+}
 
-        let mut bookmarks = Vec::new();
+/// The number rendered after each of the `displayed` bookmarks. Bookmarks ahead of the working
+/// copy always count the commits up from it.
+fn behind_counts(mode: BehindMode, displayed: &[&crate::Bookmark]) -> Vec<usize> {
+    displayed
+        .iter()
+        .map(|bookmark| match mode {
+            BehindMode::ToHead => bookmark.distance,
+            _ if bookmark.ahead => bookmark.distance,
+            BehindMode::ToNextBookmark => {
+                let next = displayed
+                    .iter()
+                    .filter(|other| !other.ahead)
+                    .map(|other| other.distance)
+                    .filter(|distance| *distance < bookmark.distance)
+                    .max()
+                    .unwrap_or(0);
+                bookmark.distance - next
+            }
+        })
+        .collect()
+}
 
-        crate::find_parent_bookmarks(workspace_helper, view, &global.bookmarks, &mut bookmarks)?;
+/// Shortens a slash-delimited `name` wider than `max_length` by abbreviating every segment but the
+/// last to its first character, or replacing them with `…` if that's still too wide. The last
+/// segment is kept whole even if it alone is wider than `max_length`.
+fn shorten_segments(name: &str, max_length: usize) -> Cow<'_, str> {
+    let Some((leading, last)) = name.rsplit_once('/') else {
+        return Cow::Borrowed(name);
+    };
+    if name.width() <= max_length {
+        return Cow::Borrowed(name);
+    }
+    let mut abbreviated: String = leading
+        .split('/')
+        .map(|segment| segment.chars().next().map(String::from).unwrap_or_default() + "/")
+        .collect();
+    abbreviated.push_str(last);
+    if abbreviated.width() <= max_length {
+        Cow::Owned(abbreviated)
+    } else {
+        Cow::Owned(format!("…/{last}"))
+    }
+}
 
-        data.bookmarks = crate::BookmarkData {
-            bookmarks: Some(bookmarks),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::bookmark;
+
+    fn render(config: &Bookmarks, bookmarks: Vec<crate::Bookmark>) -> String {
+        render_with_prefixes(config, bookmarks, Default::default())
+    }
+
+    fn render_with_prefixes(
+        config: &Bookmarks,
+        bookmarks: Vec<crate::Bookmark>,
+        target_prefix_lens: std::collections::HashMap<jj_lib::backend::CommitId, usize>,
+    ) -> String {
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(bookmarks),
+                target_prefix_lens,
+                ..Default::default()
+            },
+            ..Default::default()
         };
-        Ok(())
+        render_data(config, &data, &Default::default())
+    }
+
+    fn render_data(
+        config: &Bookmarks,
+        data: &crate::JJData,
+        global: &crate::config::GlobalConfig,
+    ) -> String {
+        crate::config::rendered(|io| config.print(io, data, global, &mut None))
+    }
+
+    #[test]
+    fn working_copy_bookmark_survives_max_bookmarks() {
+        let config = Bookmarks {
+            max_bookmarks: Some(1),
+            ..Default::default()
+        };
+
+        let out = render(
+            &config,
+            vec![bookmark("release/1.0", 3), bookmark("feature", 0)],
+        );
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35mfeature");
+    }
+
+    #[test]
+    fn max_total_length_cuts_off_before_max_bookmarks() {
+        let config = Bookmarks {
+            max_bookmarks: Some(4),
+            max_total_length: Some(16),
+            behind_symbol: None,
+            ..Default::default()
+        };
+
+        // "main feature2" is 14 wide, adding " release-candidate3" would exceed the limit.
+        let out = render(
+            &config,
+            vec![
+                bookmark("main", 0),
+                bookmark("feature", 2),
+                bookmark("release-candidate", 3),
+                bookmark("docs", 4),
+            ],
+        );
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35mmain feature2 …");
+    }
+
+    #[test]
+    fn ahead_symbol_for_descendant_bookmarks() {
+        let mut ahead = bookmark("feature", 2);
+        ahead.ahead = true;
+        let config = Bookmarks {
+            max_bookmarks: None,
+            behind_mode: BehindMode::ToNextBookmark,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&config, vec![bookmark("main", 3), ahead]),
+            "\u{1b}[0m\u{1b}[35mfeature⇣2 main⇡3"
+        );
+    }
+
+    #[test]
+    fn remote_style_applies_to_remote_bookmarks() {
+        let mut remote_style = Style::default();
+        remote_style.attributes.dimmed = Some(true);
+        let config = Bookmarks {
+            remote_style: Some(remote_style),
+            max_bookmarks: None,
+            behind_symbol: None,
+            ..Default::default()
+        };
+
+        let out = render(
+            &config,
+            vec![
+                bookmark("main", 0),
+                bookmark("main@origin", 1),
+                bookmark("feature", 2),
+            ],
+        );
+
+        assert_eq!(
+            out,
+            "\u{1b}[0m\u{1b}[35mmain \u{1b}[2mmain@origin1 \u{1b}[0m\u{1b}[0m\u{1b}[35mfeature2"
+        );
+    }
+
+    #[test]
+    fn most_recent_sorts_by_target_timestamp() {
+        let config = Bookmarks {
+            sort_by: SortBy::MostRecent,
+            max_bookmarks: None,
+            behind_symbol: None,
+            ..Default::default()
+        };
+        let mut older = bookmark("older", 2);
+        older.target = CommitId::from_hex("0001");
+        let mut newer = bookmark("newer", 2);
+        newer.target = CommitId::from_hex("0002");
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(vec![older.clone(), newer.clone()]),
+                target_timestamps: HashMap::from([
+                    (older.target.clone(), 1_700_000_000_000),
+                    (newer.target.clone(), 1_700_000_060_000),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_data(&config, &data, &Default::default()),
+            "\u{1b}[0m\u{1b}[35mnewer2 older2"
+        );
+    }
+
+    #[test]
+    fn disambiguates_names_containing_separator() {
+        let mut config = Bookmarks {
+            separator: "/".to_string(),
+            max_bookmarks: None,
+            behind_symbol: None,
+            ..Default::default()
+        };
+        let bookmarks = || vec![bookmark("feature/a", 0), bookmark("main", 0)];
+
+        assert_eq!(
+            render(&config, bookmarks()),
+            "\u{1b}[0m\u{1b}[35m\"feature/a\"/main"
+        );
+
+        config.escape_separator = true;
+        assert_eq!(
+            render(&config, bookmarks()),
+            "\u{1b}[0m\u{1b}[35mfeature\\/a/main"
+        );
+    }
+
+    #[test]
+    fn behind_to_next_bookmark_shows_gaps() {
+        let config = Bookmarks {
+            max_bookmarks: None,
+            behind_mode: BehindMode::ToNextBookmark,
+            behind_symbol: Some('+'),
+            ..Default::default()
+        };
+
+        // `@` -1- feature -3- base -2- main
+        let out = render(
+            &config,
+            vec![
+                bookmark("feature", 1),
+                bookmark("base", 4),
+                bookmark("main", 6),
+            ],
+        );
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35mfeature+1 base+3 main+2");
+    }
+
+    #[test]
+    fn keep_last_segment_abbreviates_leading_segments() {
+        let config = |max_length| Bookmarks {
+            max_length: Some(max_length),
+            keep_last_segment: true,
+            behind_symbol: None,
+            ..Default::default()
+        };
+        let render = |max_length, name| render(&config(max_length), vec![bookmark(name, 0)]);
+
+        assert_eq!(
+            render(30, "feature/backend/oauth-refresh"),
+            "\u{1b}[0m\u{1b}[35mfeature/backend/oauth-refresh"
+        );
+        assert_eq!(
+            render(20, "feature/backend/oauth-refresh"),
+            "\u{1b}[0m\u{1b}[35mf/b/oauth-refresh"
+        );
+        assert_eq!(
+            render(10, "feature/backend/oauth-refresh"),
+            "\u{1b}[0m\u{1b}[35m…/oauth-refresh"
+        );
+        assert_eq!(render(5, "main-branch"), "\u{1b}[0m\u{1b}[35mmain…");
+    }
+
+    #[test]
+    fn appends_short_target_id() {
+        let config = Bookmarks {
+            show_target_id: true,
+            max_length: Some(3),
+            ..Default::default()
+        };
+        let target = jj_lib::backend::CommitId::from_hex("abc123def456");
+        let render = |config: &Bookmarks| {
+            render_with_prefixes(
+                config,
+                vec![bookmark("feature", 0)],
+                [(target.clone(), 4)].into_iter().collect(),
+            )
+        };
+
+        assert_eq!(
+            render(&config),
+            "\u{1b}[0m\u{1b}[35mfe…\u{1b}[0m\u{1b}[0m\u{1b}[2m:abc1\u{1b}[0m\u{1b}[0m\u{1b}[35m"
+        );
+        let spaced = Bookmarks {
+            target_id_separator: " ".to_string(),
+            ..config
+        };
+        assert!(render(&spaced).contains("\u{1b}[2m abc1"));
+    }
+
+    #[test]
+    fn hides_bookmark_on_inherited_description() {
+        let parent = bookmark("feature", 1);
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(vec![parent.clone()]),
+                ..Default::default()
+            },
+            commit: crate::CommitData {
+                desc: Some("add feature".to_string()),
+                ahead: true,
+                desc_source: Some(parent.target),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let global = crate::config::GlobalConfig {
+            hide_inherited_bookmark: true,
+            ..Default::default()
+        };
+
+        assert_eq!(render_data(&Bookmarks::default(), &data, &global), "");
+        assert_eq!(
+            render_data(&Bookmarks::default(), &data, &Default::default()),
+            "\u{1b}[0m\u{1b}[35mfeature⇡1"
+        );
+    }
+
+    #[test]
+    fn tracked_marker_for_local_bookmarks() {
+        let config: Bookmarks = toml::from_str(
+            r#"
+max_bookmarks = 3
+show_tracked_marker = { tracked = "=", untracked = "?" }
+"#,
+        )
+        .unwrap();
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(vec![
+                    bookmark("main", 0),
+                    bookmark("wip", 0),
+                    bookmark("main@origin", 1),
+                ]),
+                tracking: HashMap::from([("main".to_string(), true), ("wip".to_string(), false)]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_data(&config, &data, &Default::default()),
+            "\u{1b}[0m\u{1b}[35mmain= wip? main@origin⇡1"
+        );
     }
 }
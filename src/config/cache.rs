@@ -0,0 +1,277 @@
+use std::{
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use jj_cli::command_error::{CommandError, user_error};
+use jj_lib::object_id::ObjectId as _;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Stores everything collected from the repo on disk and reuses it while the operation doesn't
+/// change, instead of recomputing it for every prompt.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct DataCache {
+    /// Reuse the data collected for the current operation.
+    #[serde(default)]
+    enabled: bool,
+    /// File the data is stored in. Defaults to a file per workspace in starship-jj's cache
+    /// directory.
+    #[serde(default)]
+    path: Option<PathBuf>,
+}
+
+/// Where the data for the current operation is cached and the key it is stored under.
+pub(super) struct CacheEntry {
+    path: PathBuf,
+    key: String,
+}
+
+impl DataCache {
+    /// The cache entry for the current operation, workspace and `config`. `None` if caching is
+    /// disabled or the repo can't be loaded, in which case the error resurfaces when the modules
+    /// are parsed.
+    pub(super) fn entry(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        config: &super::Config,
+    ) -> Option<CacheEntry> {
+        if !self.enabled {
+            return None;
+        }
+        let op_id = state.repo(command_helper).ok()?.op_id().hex();
+        let workspace_root = state
+            .workspace_helper(command_helper)
+            .ok()?
+            .workspace_root();
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => super::util::cache_file("data", workspace_root)?,
+        };
+        // Modules only collect what they render, so data collected for another config can't be
+        // reused.
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(config).ok()?.hash(&mut hasher);
+        let key = format!(
+            "{op_id} {:016x} {}",
+            hasher.finish(),
+            workspace_root.display()
+        );
+        Some(CacheEntry { path, key })
+    }
+}
+
+impl CacheEntry {
+    /// The data stored for this entry, `None` if there is none or it was stored for another key.
+    pub(super) fn load(&self) -> Option<crate::JJData> {
+        let cached = std::fs::read_to_string(&self.path).ok()?;
+        parse_cache(&cached, &self.key)
+    }
+
+    /// Best effort: the prompt is still printed if the cache can't be written, it's only slower
+    /// next time.
+    pub(super) fn store(&self, data: &crate::JJData) {
+        let Ok(cache) = format_cache(&self.key, data) else {
+            return;
+        };
+        if let Some(dir) = self.path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(&self.path, cache);
+    }
+
+    #[cfg(test)]
+    fn new(path: &Path, key: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            key: key.to_string(),
+        }
+    }
+}
+
+/// Records when the working copy was last snapshotted, so the snapshot can be skipped while it is
+/// recent.
+pub(super) struct SnapshotStamp {
+    path: PathBuf,
+}
+
+impl SnapshotStamp {
+    /// The stamp of the workspace the prompt is rendered for, `None` outside of a workspace.
+    pub(super) fn new(command_helper: &jj_cli::cli_util::CommandHelper) -> Option<Self> {
+        let workspace_root = command_helper.workspace_loader().ok()?.workspace_root();
+        Some(Self {
+            path: super::util::cache_file("snapshot", workspace_root)?,
+        })
+    }
+
+    /// Whether the last snapshot was taken less than `interval_ms` milliseconds ago.
+    pub(super) fn is_recent(&self, interval_ms: u64) -> bool {
+        is_recent(&self.path, interval_ms, now_millis())
+    }
+
+    /// Best effort: without the stamp the next prompt just snapshots again.
+    pub(super) fn record(&self) {
+        record(&self.path, now_millis())
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Stamp files hold the time of the last snapshot in milliseconds since the epoch.
+fn is_recent(path: &Path, interval_ms: u64, now: u64) -> bool {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|stamp| stamp.trim().parse::<u64>().ok())
+        .is_some_and(|last| now.saturating_sub(last) < interval_ms)
+}
+
+fn record(path: &Path, now: u64) {
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = std::fs::write(path, format!("{now}\n"));
+}
+
+/// Cache files hold the key on the first line, followed by the data as JSON.
+fn format_cache(key: &str, data: &crate::JJData) -> Result<String, CommandError> {
+    let data = serde_json::to_string(data).map_err(user_error)?;
+    Ok(format!("{key}\n{data}\n"))
+}
+
+fn parse_cache(s: &str, key: &str) -> Option<crate::JJData> {
+    let (cached_key, data) = s.split_once('\n')?;
+    if cached_key != key {
+        return None;
+    }
+    serde_json::from_str(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_is_recent_within_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot");
+        let missing = is_recent(&path, 1000, 5000);
+        record(&path, 5000);
+        let recent = is_recent(&path, 1000, 5999);
+        let stale = is_recent(&path, 1000, 6000);
+
+        assert!(!missing);
+        assert!(recent);
+        assert!(!stale);
+    }
+
+    #[test]
+    fn unwritable_cache_is_skipped() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let entry = CacheEntry::new(&file.path().join("data"), "op1");
+        entry.store(&crate::JJData::default());
+        let loaded = entry.load();
+
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn data_is_reused_for_the_same_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data");
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                desc: Some("fix the parser".to_string()),
+                change_id: Some((jj_lib::backend::ChangeId::from_hex("0123abcd"), 2)),
+                commit_id: Some((jj_lib::backend::CommitId::from_hex("4567ef01"), 3)),
+                diff: Some(crate::CommitDiff {
+                    files_changed: 3,
+                    lines_added: 10,
+                    lines_removed: 2,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        CacheEntry::new(&path, "op1").store(&data);
+        let hit = CacheEntry::new(&path, "op1").load();
+        let miss = CacheEntry::new(&path, "op2").load();
+
+        let render = |data: &crate::JJData| {
+            let config = toml::from_str::<super::super::Config>(
+                r#"
+[[module]]
+type = "ChangeId"
+
+[[module]]
+type = "CommitId"
+
+[[module]]
+type = "Commit"
+
+[[module]]
+type = "Metrics"
+"#,
+            )
+            .unwrap();
+            crate::config::rendered(|io| config.print_data(data, io))
+        };
+        assert_eq!(render(&hit.unwrap()), render(&data));
+        assert!(miss.is_none());
+    }
+
+    #[test]
+    fn no_cache_modules_recompute_only_their_cached_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data");
+        let config = toml::from_str::<super::super::Config>(
+            r#"
+[[module]]
+type = "Bookmarks"
+
+[[module]]
+type = "State"
+no_cache = true
+
+[module.immutable]
+text = "(IMMUTABLE)"
+disabled = true
+"#,
+        )
+        .unwrap();
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(Vec::new()),
+                ..Default::default()
+            },
+            commit: crate::CommitData {
+                warnings: crate::CommitWarnings {
+                    conflict: Some(true),
+                    immutable: Some(true),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        CacheEntry::new(&path, "op1").store(&data);
+        let mut cached = CacheEntry::new(&path, "op1").load().unwrap();
+        for module in config.modules.iter().filter(|module| module.no_cache) {
+            module.module.invalidate(&mut cached);
+        }
+
+        assert!(cached.bookmarks.bookmarks.is_some());
+        assert_eq!(cached.commit.warnings.conflict, None);
+        // Resolved by another module, the `State` module has it switched off.
+        assert_eq!(cached.commit.warnings.immutable, Some(true));
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr as _;
+
+use super::util::{Color, Style};
+
+/// Prints the shortest unique prefix of the working copy's change id, like `jj log` highlights it.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ChangeId {
+    /// Show at least this many characters instead of only the unique prefix.
+    #[serde(default)]
+    prefix_length: Option<usize>,
+    /// Show the whole change id.
+    #[serde(default)]
+    full: bool,
+    /// Pad the rendered change id with spaces to this width, so ids with shorter unique prefixes
+    /// still line up.
+    #[serde(default)]
+    pad_to: Option<usize>,
+    /// Which side of the change id the padding is added to.
+    #[serde(default)]
+    pad_side: PadSide,
+    /// Controls how the part of the change id after the unique prefix is rendered.
+    #[serde(default = "default_non_unique_style")]
+    non_unique: Style,
+    /// Controls how the unique prefix is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PadSide {
+    /// Left => Add the spaces before the id, aligning it to the right
+    Left,
+    /// Right => [default] Add the spaces after the id, aligning it to the left
+    #[default]
+    Right,
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Magenta),
+        ..Default::default()
+    }
+}
+
+fn default_non_unique_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl Default for ChangeId {
+    fn default() -> Self {
+        Self {
+            prefix_length: None,
+            full: false,
+            pad_to: None,
+            pad_side: PadSide::default(),
+            non_unique: default_non_unique_style(),
+            style: default_style(),
+        }
+    }
+}
+
+impl ChangeId {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some((change_id, unique_len)) = &data.commit.change_id else {
+            return Ok(());
+        };
+
+        // Change ids use jj's reverse hex alphabet (`z`-`k`) so they match `jj log`.
+        print_id(
+            io,
+            &change_id.reverse_hex(),
+            *unique_len,
+            self.prefix_length,
+            self.full,
+            self.pad_to.map(|width| (width, self.pad_side)),
+            (&self.style, default_style()),
+            (&self.non_unique, default_non_unique_style()),
+            prev_style,
+        )
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        super::commit::resolve_change_id(command_helper, state, data)
+    }
+}
+
+/// Prints the unique prefix of `id`, followed by the rest up to `prefix_length` characters (or all
+/// of them if `full`) in the `non_unique` style, padded with spaces to the given display width.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn print_id(
+    io: &mut impl Write,
+    id: &str,
+    unique_len: usize,
+    prefix_length: Option<usize>,
+    full: bool,
+    pad: Option<(usize, PadSide)>,
+    (style, default_style): (&Style, Style),
+    (non_unique, default_non_unique_style): (&Style, Style),
+    prev_style: &mut Option<nu_ansi_term::Style>,
+) -> Result<(), CommandError> {
+    let len = if full {
+        id.len()
+    } else {
+        prefix_length.unwrap_or_default().max(unique_len)
+    };
+    let shown = &id[..len.min(id.len())];
+    let (unique, rest) = shown.split_at(unique_len.min(shown.len()));
+    let (padding, side) = pad
+        .map(|(width, side)| (width.saturating_sub(shown.width()), side))
+        .unwrap_or((0, PadSide::Right));
+
+    style.print(io, default_style, prev_style)?;
+    if side == PadSide::Left {
+        write!(io, "{:padding$}", "")?;
+    }
+    write!(io, "{unique}")?;
+    if !rest.is_empty() {
+        non_unique.print(io, default_non_unique_style, prev_style)?;
+        write!(io, "{rest}")?;
+    }
+    if side == PadSide::Right {
+        write!(io, "{:padding$}", "")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &ChangeId) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                // `jj log` shows this change as `zyxwvutsrqponmlk...`.
+                change_id: Some((
+                    jj_lib::backend::ChangeId::from_hex("0123456789abcdef0123456789abcdef"),
+                    3,
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn truncates_to_unique_prefix() {
+        assert_eq!(render(&ChangeId::default()), "\u{1b}[0m\u{1b}[35mzyx");
+
+        let config = ChangeId {
+            prefix_length: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(render(&config), "\u{1b}[0m\u{1b}[35mzyx\u{1b}[90mwvuts");
+
+        let config = ChangeId {
+            prefix_length: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(render(&config), "\u{1b}[0m\u{1b}[35mzyx");
+
+        let config = ChangeId {
+            full: true,
+            ..Default::default()
+        };
+        assert!(render(&config).ends_with("wvutsrqponmlkzyxwvutsrqponmlk"));
+    }
+
+    #[test]
+    fn pads_to_fixed_width() {
+        let config = ChangeId {
+            prefix_length: Some(4),
+            pad_to: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(render(&config), "\u{1b}[0m\u{1b}[35mzyx\u{1b}[90mw    ");
+
+        let config = ChangeId {
+            prefix_length: Some(4),
+            pad_to: Some(8),
+            pad_side: PadSide::Left,
+            ..Default::default()
+        };
+        assert_eq!(render(&config), "\u{1b}[0m\u{1b}[35m    zyx\u{1b}[90mw");
+
+        let config = ChangeId {
+            pad_to: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(render(&config), "\u{1b}[0m\u{1b}[35mzyx");
+    }
+}
@@ -1,14 +1,14 @@
-use std::io::Write;
+use std::{borrow::Cow, io::Write};
 
 use jj_cli::command_error::CommandError;
-use jj_lib::id_prefix::IdPrefixIndex;
+use jj_lib::{id_prefix::IdPrefixIndex, object_id::ObjectId as _};
 #[cfg(feature = "json-schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::config::util::Color;
 
-use super::util::Style;
+use super::util::{Regex, Style};
 
 /// Prints the working copy's commit text.
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
@@ -21,6 +21,9 @@ pub struct Commit {
     /// Maximum length the commit text will be truncated to.
     #[serde(default = "default_max_length")]
     max_length: Option<usize>,
+    /// End a truncated subject on a whole word, unless its first word alone is too long.
+    #[serde(default)]
+    truncate_words: bool,
     /// Show the previous commits description in case current is empty
     /// This will also print the previous_message_symbol
     #[serde(default)]
@@ -28,14 +31,53 @@ pub struct Commit {
     /// The text that should be printed when the current revision has no description yet.
     #[serde(default = "default_empty_text")]
     empty_text: String,
+    /// The text that should be printed instead of the description when the working copy is the
+    /// root commit.
+    #[serde(default = "default_root_text")]
+    root_text: String,
+    /// Descriptions that entirely match one of these regular expressions are treated as empty,
+    /// e.g. template text inserted by tooling.
+    #[serde(default)]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Vec<String>"))]
+    placeholder_patterns: Vec<Regex>,
+    /// Use the first line that isn't blank or a comment as the subject instead of the literal
+    /// first line.
+    #[serde(default)]
+    skip_blank_lines: bool,
+    /// Lines starting with this are treated as comments when `skip_blank_lines` is set.
+    #[serde(default = "default_comment_prefix")]
+    comment_prefix: String,
+    /// Remove a trailing block of `Key: value` lines, like `Signed-off-by:` or `Change-Id:`, from
+    /// the description.
+    #[serde(default)]
+    strip_trailers: bool,
+    /// Render up to this many non-empty description lines instead of only the subject.
+    #[serde(default)]
+    max_lines: Option<usize>,
+    /// The text that joins description lines when `max_lines` is greater than 1.
+    #[serde(default = "default_line_separator")]
+    line_separator: String,
     /// Controls how the commit text is rendered.
     #[serde(flatten)]
     style: Style,
     /// Render quotes around the description.
     #[serde(default = "default_surround_with_quotes")]
     surround_with_quotes: bool,
+    /// Changes the case of the commit subject before it is truncated.
+    #[serde(default)]
+    case: Case,
+    /// A symbol that will be printed before the subject when the commit has a description.
+    #[serde(default)]
+    prefix_symbol: Option<String>,
+    /// Controls how the prefix_symbol is rendered.
+    #[serde(default)]
+    prefix_symbol_style: Style,
     /// Controls if and how the Change Id should be shown
     change: Option<Style>,
+    /// Controls how the Change Id is shown when the commit was authored by you.
+    change_mine: Option<Style>,
+    /// Controls how the Change Id is shown when the commit was authored by someone else.
+    change_others: Option<Style>,
     /// Controls if and how the Commit Id should be shown
     commit: Option<Style>,
     /// Controls how the non unique part of  Ids should be shown
@@ -43,6 +85,43 @@ pub struct Commit {
     non_unique: Style,
 }
 
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    /// Preserve => [default] Render the subject as written
+    #[default]
+    Preserve,
+    /// Lower => Render the subject in lowercase
+    Lower,
+    /// Upper => Render the subject in uppercase
+    Upper,
+    /// Title => Capitalize the first letter of every word and lowercase the rest
+    Title,
+}
+
+impl Case {
+    fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Case::Preserve => Cow::Borrowed(text),
+            Case::Lower => Cow::Owned(text.chars().flat_map(char::to_lowercase).collect()),
+            Case::Upper => Cow::Owned(text.chars().flat_map(char::to_uppercase).collect()),
+            Case::Title => {
+                let mut out = String::with_capacity(text.len());
+                let mut start_of_word = true;
+                for c in text.chars() {
+                    if start_of_word {
+                        out.extend(c.to_uppercase());
+                    } else {
+                        out.extend(c.to_lowercase());
+                    }
+                    start_of_word = c.is_whitespace();
+                }
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
 fn default_non_unique_style() -> Style {
     Style {
         color: Some(Color::Black),
@@ -70,10 +149,21 @@ fn default_previous_message_symbol() -> char {
 fn default_max_length() -> Option<usize> {
     Some(20)
 }
+fn default_comment_prefix() -> String {
+    "#".to_string()
+}
+fn default_line_separator() -> String {
+    " · ".to_string()
+}
+
 fn default_empty_text() -> String {
     "󰆇".to_string()
 }
 
+fn default_root_text() -> String {
+    "root()".to_string()
+}
+
 fn default_surround_with_quotes() -> bool {
     false
 }
@@ -84,35 +174,116 @@ impl Default for Commit {
             show_previous_if_empty: false,
             style: Default::default(),
             max_length: default_max_length(),
+            truncate_words: false,
             empty_text: default_empty_text(),
+            root_text: default_root_text(),
+            placeholder_patterns: Vec::new(),
+            skip_blank_lines: false,
+            comment_prefix: default_comment_prefix(),
+            strip_trailers: false,
+            max_lines: None,
+            line_separator: default_line_separator(),
             surround_with_quotes: false,
+            case: Default::default(),
+            prefix_symbol: None,
+            prefix_symbol_style: Default::default(),
             previous_message_symbol: default_previous_message_symbol(),
             commit: None,
             change: None,
+            change_mine: None,
+            change_others: None,
             non_unique: default_non_unique_style(),
         }
     }
 }
 
 impl Commit {
+    /// Prints the first 8 characters of `id`, styling everything after the unique prefix as non unique.
+    fn print_short_id(
+        &self,
+        io: &mut impl Write,
+        id: &str,
+        unique_len: usize,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let short_id = &id[..8.min(id.len())];
+        let (unique, non_unique) = short_id.split_at(unique_len.min(short_id.len()));
+        write!(io, "{unique}")?;
+        self.non_unique
+            .print(io, default_non_unique_style(), prev_style)?;
+        write!(io, "{non_unique}")?;
+        Ok(())
+    }
+
+    fn is_placeholder(&self, desc: &str) -> bool {
+        let desc = desc.trim_end();
+        self.placeholder_patterns
+            .iter()
+            .any(|pattern| pattern.matches(desc))
+    }
+
+    fn is_comment(&self, line: &str) -> bool {
+        self.skip_blank_lines
+            && !self.comment_prefix.is_empty()
+            && line.trim_start().starts_with(&self.comment_prefix)
+    }
+
+    /// The line of `desc` that is rendered, empty if there is none.
+    fn subject<'a>(&self, desc: &'a str) -> &'a str {
+        if !self.skip_blank_lines {
+            return desc
+                .split_once(['\r', '\n'])
+                .map(|(line, _rest)| line)
+                .unwrap_or(desc);
+        }
+        desc.lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .find(|line| !line.trim().is_empty() && !self.is_comment(line))
+            .unwrap_or("")
+    }
+
+    /// The text of `desc` that is rendered: the subject, or up to `max_lines` non-empty lines
+    /// joined by `line_separator`.
+    fn text<'a>(&self, desc: &'a str) -> Cow<'a, str> {
+        match self.max_lines {
+            Some(max_lines) if max_lines > 1 => Cow::Owned(
+                desc.lines()
+                    .map(|line| line.trim_end_matches('\r'))
+                    .filter(|line| !line.trim().is_empty() && !self.is_comment(line))
+                    .take(max_lines)
+                    .collect::<Vec<_>>()
+                    .join(&self.line_separator),
+            ),
+            _ => Cow::Borrowed(self.subject(desc)),
+        }
+    }
+
     pub fn print(
         &self,
         io: &mut impl Write,
         data: &crate::JJData,
-        module_separator: &str,
+        global: &super::GlobalConfig,
         prev_style: &mut Option<nu_ansi_term::Style>,
     ) -> Result<(), CommandError> {
         let mut first = true;
         if let (Some(change), Some((change_id, change_idx))) =
             (&self.change, &data.commit.change_id)
         {
-            change.print(io, default_unique_change_style(), prev_style)?;
-            let short_change_id = &change_id.to_string()[..8];
-            let (unique, non_unique) = short_change_id.split_at(*change_idx);
-            write!(io, "{unique}")?;
-            self.non_unique
-                .print(io, default_non_unique_style(), prev_style)?;
-            write!(io, "{non_unique}")?;
+            let author_style = match data.commit.mine {
+                Some(true) => self.change_mine.as_ref(),
+                Some(false) => self.change_others.as_ref(),
+                None => None,
+            };
+            match author_style {
+                Some(author_style) => author_style.print(
+                    io,
+                    change.merge_with_fallback(Some(default_unique_change_style())),
+                    prev_style,
+                )?,
+                None => change.print(io, default_unique_change_style(), prev_style)?,
+            }
+            // Change ids use jj's reverse hex alphabet (`z`-`k`) so they match `jj log`.
+            self.print_short_id(io, &change_id.reverse_hex(), *change_idx, prev_style)?;
             first = false;
         }
         if let (Some(commit), Some((commit_id, commit_idx))) =
@@ -122,35 +293,65 @@ impl Commit {
                 write!(io, " ")?;
             }
             commit.print(io, default_unique_commit_style(), prev_style)?;
-            let short_commit_id = &commit_id.to_string()[..8];
-            let (unique, non_unique) = short_commit_id.split_at(*commit_idx);
-            write!(io, "{unique}")?;
-            self.non_unique
-                .print(io, default_non_unique_style(), prev_style)?;
-            write!(io, "{non_unique}")?;
+            self.print_short_id(io, &commit_id.hex(), *commit_idx, prev_style)?;
             first = false;
         }
 
-        let Some(desc) = data.commit.desc.as_ref() else {
+        if data.commit.root == Some(true) {
+            if !first {
+                write!(io, " ")?;
+            }
+            self.style.print(io, None, prev_style)?;
+            crate::print_ansi_truncated(
+                self.max_length,
+                io,
+                &self.root_text,
+                false,
+                global.escape,
+            )?;
             return Ok(());
+        }
+
+        let Some(desc) = data.commit.desc.as_deref() else {
+            return Ok(());
+        };
+        let desc = if self.is_placeholder(desc) { "" } else { desc };
+        let first_line = self.subject(desc);
+        // Descriptions made up of blank and comment lines only are rendered as empty.
+        let desc = if self.skip_blank_lines && first_line.is_empty() {
+            ""
+        } else {
+            desc
         };
 
         if !first {
             write!(io, " ")?;
         }
-        let first_line = desc
-            .split_once(['\r', '\n'])
-            .map(|(line, _rest)| line)
-            .unwrap_or(desc);
+
+        if !desc.is_empty()
+            && let Some(prefix_symbol) = &self.prefix_symbol
+        {
+            self.prefix_symbol_style.print(io, None, prev_style)?;
+            write!(io, "{prefix_symbol}")?;
+        }
 
         self.style.print(io, None, prev_style)?;
 
-        if !desc.is_empty() {
+        if !desc.is_empty() && self.truncate_words {
+            crate::print_ansi_truncated_words(
+                self.max_length,
+                io,
+                &self.case.apply(&self.text(desc)),
+                self.surround_with_quotes,
+                global.escape,
+            )?;
+        } else if !desc.is_empty() {
             crate::print_ansi_truncated(
                 self.max_length,
                 io,
-                first_line,
+                &self.case.apply(&self.text(desc)),
                 self.surround_with_quotes,
+                global.escape,
             )?;
         } else {
             crate::print_ansi_truncated(
@@ -158,12 +359,12 @@ impl Commit {
                 io,
                 &self.empty_text,
                 self.surround_with_quotes,
+                global.escape,
             )?;
         }
         if data.commit.ahead {
             write!(io, "{}", self.previous_message_symbol)?;
         }
-        write!(io, "{module_separator}")?;
         Ok(())
     }
     pub(crate) fn parse(
@@ -173,57 +374,36 @@ impl Commit {
         data: &mut crate::JJData,
         global: &super::GlobalConfig,
     ) -> Result<(), CommandError> {
+        resolve_root(command_helper, state, data)?;
         self.resolve_desc(command_helper, state, data, global)?;
 
         if self.commit.is_some() {
-            self.resolve_commit_id(command_helper, state, data, global)?;
+            resolve_commit_id(command_helper, state, data)?;
         }
         if self.change.is_some() {
-            self.resolve_change_id(command_helper, state, data, global)?;
+            resolve_change_id(command_helper, state, data)?;
         }
-
-        Ok(())
-    }
-
-    fn resolve_commit_id(
-        &self,
-        command_helper: &jj_cli::cli_util::CommandHelper,
-        state: &mut crate::State,
-        data: &mut crate::JJData,
-        _global: &super::GlobalConfig,
-    ) -> Result<(), CommandError> {
-        if data.commit.commit_id.is_some() {
-            return Ok(());
+        if self.change.is_some() && (self.change_mine.is_some() || self.change_others.is_some()) {
+            self.resolve_mine(command_helper, state, data)?;
         }
-        let repo = state.repo(command_helper)?;
-        let Some(commit) = state.commit(command_helper)? else {
-            return Ok(());
-        };
-        let commit_id = commit.id().clone();
-        let commit_idx =
-            IdPrefixIndex::empty().shortest_commit_prefix_len(repo.as_ref(), &commit_id)?;
-        data.commit.commit_id = Some((commit_id, commit_idx));
+
         Ok(())
     }
 
-    fn resolve_change_id(
+    fn resolve_mine(
         &self,
         command_helper: &jj_cli::cli_util::CommandHelper,
         state: &mut crate::State,
         data: &mut crate::JJData,
-        _global: &super::GlobalConfig,
     ) -> Result<(), CommandError> {
-        if data.commit.change_id.is_some() {
+        if data.commit.mine.is_some() {
             return Ok(());
         }
-        let repo = state.repo(command_helper)?;
+        let user = state.user(command_helper)?.clone();
         let Some(commit) = state.commit(command_helper)? else {
             return Ok(());
         };
-        let change_id = commit.change_id().clone();
-        let change_idx =
-            IdPrefixIndex::empty().shortest_change_prefix_len(repo.as_ref(), &change_id)?;
-        data.commit.change_id = Some((change_id, change_idx));
+        data.commit.mine = Some(user.is_author(commit.author()));
         Ok(())
     }
 
@@ -241,7 +421,11 @@ impl Commit {
             return Ok(());
         };
 
-        let description = commit.description().to_string();
+        let description = if self.strip_trailers {
+            strip_trailers(commit.description()).to_string()
+        } else {
+            commit.description().to_string()
+        };
         if description.is_empty() && self.show_previous_if_empty {
             let parents = state.parent_commits(command_helper)?;
             if parents.len() != 1 {
@@ -250,11 +434,284 @@ impl Commit {
 
             let parent = parents.first().expect("We already checked the vec length");
 
-            data.commit.desc = Some(parent.description().to_string());
+            data.commit.desc = Some(if self.strip_trailers {
+                strip_trailers(parent.description()).to_string()
+            } else {
+                parent.description().to_string()
+            });
             data.commit.ahead = true;
+            data.commit.desc_source = Some(parent.id().clone());
         } else {
             data.commit.desc = Some(description);
         }
         Ok(())
     }
 }
+
+/// Removes the last paragraph of `desc` if it consists only of `Key: value` trailer lines. The
+/// subject is never treated as trailers.
+fn strip_trailers(desc: &str) -> &str {
+    let body = desc.trim_end();
+    let Some((rest, last_paragraph)) = body.rsplit_once("\n\n") else {
+        return desc;
+    };
+    let is_trailer = |line: &str| {
+        line.trim_end_matches('\r')
+            .split_once(": ")
+            .is_some_and(|(key, value)| {
+                !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    && !value.trim().is_empty()
+            })
+    };
+    if last_paragraph.lines().all(is_trailer) {
+        rest.trim_end()
+    } else {
+        desc
+    }
+}
+
+/// Resolves whether the working copy commit has a description of its own, ignoring whitespace.
+pub(super) fn resolve_described(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+) -> Result<(), CommandError> {
+    if data.commit.described.is_none() {
+        data.commit.described = state
+            .commit(command_helper)?
+            .as_ref()
+            .map(|commit| !commit.description().trim().is_empty());
+    }
+    Ok(())
+}
+
+/// Resolves whether the working copy is the root commit.
+pub(super) fn resolve_root(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+) -> Result<(), CommandError> {
+    if data.commit.root.is_none() {
+        data.commit.root = Some(state.is_root(command_helper)?);
+    }
+    Ok(())
+}
+
+/// Resolves the working copy's commit id together with the length of its shortest unique prefix.
+pub(super) fn resolve_commit_id(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+) -> Result<(), CommandError> {
+    if data.commit.commit_id.is_some() {
+        return Ok(());
+    }
+    let repo = state.repo(command_helper)?;
+    let Some(commit) = state.commit(command_helper)? else {
+        return Ok(());
+    };
+    let commit_id = commit.id().clone();
+    let commit_idx =
+        IdPrefixIndex::empty().shortest_commit_prefix_len(repo.as_ref(), &commit_id)?;
+    data.commit.commit_id = Some((commit_id, commit_idx));
+    Ok(())
+}
+
+/// Resolves the working copy's change id together with the length of its shortest unique prefix.
+pub(super) fn resolve_change_id(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+) -> Result<(), CommandError> {
+    if data.commit.change_id.is_some() {
+        return Ok(());
+    }
+    let repo = state.repo(command_helper)?;
+    let Some(commit) = state.commit(command_helper)? else {
+        return Ok(());
+    };
+    let change_id = commit.change_id().clone();
+    let change_idx =
+        IdPrefixIndex::empty().shortest_change_prefix_len(repo.as_ref(), &change_id)?;
+    data.commit.change_id = Some((change_id, change_idx));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBJECT: &str = "fIX the ÄRGER in überall";
+
+    #[test]
+    fn case_preserve() {
+        assert_eq!(Case::Preserve.apply(SUBJECT), SUBJECT);
+    }
+
+    #[test]
+    fn case_lower() {
+        assert_eq!(Case::Lower.apply(SUBJECT), "fix the ärger in überall");
+    }
+
+    #[test]
+    fn case_upper() {
+        assert_eq!(Case::Upper.apply(SUBJECT), "FIX THE ÄRGER IN ÜBERALL");
+    }
+
+    #[test]
+    fn case_title() {
+        assert_eq!(Case::Title.apply(SUBJECT), "Fix The Ärger In Überall");
+    }
+
+    #[test]
+    fn change_id_uses_reverse_hex() {
+        let config = Commit {
+            change: Some(Default::default()),
+            ..Default::default()
+        };
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                // `jj log` shows this change as `zyxwvutsrqponmlk...`.
+                change_id: Some((
+                    jj_lib::backend::ChangeId::from_hex("0123456789abcdef0123456789abcdef"),
+                    2,
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let out = crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        });
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35mzy\u{1b}[30mxwvuts");
+    }
+
+    fn render_desc(config: &Commit, desc: &str) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                desc: Some(desc.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn skip_blank_lines_uses_first_content_line() {
+        let mut config = Commit {
+            empty_text: "empty".to_string(),
+            ..Default::default()
+        };
+        assert!(!render_desc(&config, "\n\nfix parser\n").contains("fix parser"));
+
+        config.skip_blank_lines = true;
+        assert!(render_desc(&config, "\n  \nfix parser\n").ends_with("mfix parser"));
+        assert!(render_desc(&config, "# ticket 12\nfix parser\n").ends_with("mfix parser"));
+        assert!(render_desc(&config, "\r\n# note\r\nfix parser\r\n").ends_with("mfix parser"));
+        assert!(render_desc(&config, "\n# only a comment\n").ends_with("mempty"));
+
+        config.comment_prefix = "JJ:".to_string();
+        assert!(render_desc(&config, "JJ: note\n# heading\n").ends_with("m# heading"));
+    }
+
+    #[test]
+    fn truncate_words_ends_on_whole_word() {
+        let config = Commit {
+            max_length: Some(12),
+            truncate_words: true,
+            ..Default::default()
+        };
+
+        assert!(render_desc(&config, "fix the parser again").ends_with("mfix the…"));
+        assert!(render_desc(&config, "fix the lexer").ends_with("mfix the…"));
+        assert!(render_desc(&config, "fix the lex now").ends_with("mfix the lex…"));
+        assert!(render_desc(&config, "internationalization").ends_with("minternation…"));
+        assert!(render_desc(&config, "short one").ends_with("mshort one"));
+    }
+
+    #[test]
+    fn max_lines_joins_non_empty_lines() {
+        let mut config = Commit {
+            max_length: None,
+            ..Default::default()
+        };
+        let desc = "fix parser\n\nhandle nested quotes\r\nadd tests\n";
+        assert!(render_desc(&config, desc).ends_with("mfix parser"));
+
+        config.max_lines = Some(2);
+        assert!(render_desc(&config, desc).ends_with("mfix parser · handle nested quotes"));
+
+        config.max_lines = Some(5);
+        config.line_separator = " / ".to_string();
+        config.max_length = Some(20);
+        assert!(render_desc(&config, desc).ends_with("mfix parser / handle…"));
+    }
+
+    #[test]
+    fn strips_trailing_trailers() {
+        assert_eq!(
+            strip_trailers(
+                "fix parser\n\nNested quotes: were dropped.\n\nSigned-off-by: Jane <j@x>\nChange-Id: I0123\n"
+            ),
+            "fix parser\n\nNested quotes: were dropped."
+        );
+        // A last paragraph with any other line is body text.
+        let desc = "fix parser\n\nSee-also: #12\nand some more text\n";
+        assert_eq!(strip_trailers(desc), desc);
+        // The subject alone is never a trailer.
+        assert_eq!(strip_trailers("Fixes: the parser"), "Fixes: the parser");
+    }
+
+    #[test]
+    fn prefix_symbol_only_for_descriptions() {
+        let config = Commit {
+            prefix_symbol: Some("✎ ".to_string()),
+            prefix_symbol_style: Style {
+                color: Some(Color::Yellow),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_desc(&config, "fix things"),
+            "\u{1b}[0m\u{1b}[33m✎ \u{1b}[0m\u{1b}[0m\u{1b}[mfix things"
+        );
+        assert!(!render_desc(&config, "").contains('✎'));
+    }
+
+    #[test]
+    fn placeholder_description_renders_as_empty() {
+        let config: Commit = toml::from_str(
+            r#"
+empty_text = "(no description)"
+placeholder_patterns = ["TODO: describe.*", "JJ: .*"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            render_desc(&config, "TODO: describe this change\n"),
+            "\u{1b}[0m\u{1b}[m(no description)"
+        );
+        assert_eq!(
+            render_desc(&config, "fix: TODO: describe"),
+            "\u{1b}[0m\u{1b}[mfix: TODO: describe"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+use jj_lib::object_id::ObjectId as _;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    change_id::PadSide,
+    util::{Color, Style},
+};
+
+/// Prints the shortest unique prefix of the working copy's commit id, like `jj log` highlights it.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CommitId {
+    /// Show at least this many characters instead of only the unique prefix.
+    #[serde(default)]
+    prefix_length: Option<usize>,
+    /// Show the whole commit id.
+    #[serde(default)]
+    full: bool,
+    /// Pad the rendered commit id with spaces to this width, so ids with shorter unique prefixes
+    /// still line up.
+    #[serde(default)]
+    pad_to: Option<usize>,
+    /// Which side of the commit id the padding is added to.
+    #[serde(default)]
+    pad_side: PadSide,
+    /// Controls how the part of the commit id after the unique prefix is rendered.
+    #[serde(default = "default_non_unique_style")]
+    non_unique: Style,
+    /// Controls how the unique prefix is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Blue),
+        ..Default::default()
+    }
+}
+
+fn default_non_unique_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl Default for CommitId {
+    fn default() -> Self {
+        Self {
+            prefix_length: None,
+            full: false,
+            pad_to: None,
+            pad_side: PadSide::default(),
+            non_unique: default_non_unique_style(),
+            style: default_style(),
+        }
+    }
+}
+
+impl CommitId {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some((commit_id, unique_len)) = &data.commit.commit_id else {
+            return Ok(());
+        };
+
+        super::change_id::print_id(
+            io,
+            &commit_id.hex(),
+            *unique_len,
+            self.prefix_length,
+            self.full,
+            self.pad_to.map(|width| (width, self.pad_side)),
+            (&self.style, default_style()),
+            (&self.non_unique, default_non_unique_style()),
+            prev_style,
+        )
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        super::commit::resolve_commit_id(command_helper, state, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &CommitId, commit_id: Option<(&'static str, usize)>) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                commit_id: commit_id
+                    .map(|(hex, len)| (jj_lib::backend::CommitId::from_hex(hex), len)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn truncates_to_unique_prefix() {
+        let id = Some(("0123abcd4567ef89", 4));
+        assert_eq!(render(&CommitId::default(), id), "\u{1b}[0m\u{1b}[34m0123");
+
+        let config = CommitId {
+            prefix_length: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(render(&config, id), "\u{1b}[0m\u{1b}[34m0123\u{1b}[90mabcd");
+    }
+
+    #[test]
+    fn renders_nothing_without_commit() {
+        assert_eq!(render(&CommitId::default(), None), "");
+    }
+}
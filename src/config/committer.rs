@@ -0,0 +1,128 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    author::{Show, print_signature, resolve_signatures},
+    util::{Color, Style},
+};
+
+/// Prints the committer of the working copy commit.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Committer {
+    /// Which part of the committer signature is rendered.
+    #[serde(default)]
+    show: Show,
+    /// Maximum length the committer will be truncated to.
+    #[serde(default)]
+    max_length: Option<usize>,
+    /// Text that will be printed after the committer when the author is someone else.
+    #[serde(default)]
+    mismatch_symbol: Option<String>,
+    /// Controls how the committer is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Yellow),
+        ..Default::default()
+    }
+}
+
+impl Default for Committer {
+    fn default() -> Self {
+        Self {
+            show: Default::default(),
+            max_length: None,
+            mismatch_symbol: None,
+            style: default_style(),
+        }
+    }
+}
+
+impl Committer {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        print_signature(
+            io,
+            data.commit.committer.as_ref(),
+            data,
+            self.show,
+            self.max_length,
+            self.mismatch_symbol.as_deref(),
+            &self.style,
+            default_style(),
+            global,
+            prev_style,
+        )
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        resolve_signatures(command_helper, state, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::author::Author;
+
+    fn signature(name: &str, email: &str) -> Option<crate::CommitSignature> {
+        Some(crate::CommitSignature {
+            name: name.to_string(),
+            email: email.to_string(),
+        })
+    }
+
+    #[test]
+    fn renders_own_signature_and_mismatch() {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                author: signature("Jane Doe", "jane@example.com"),
+                committer: signature("John Roe", "john@example.com"),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let global = crate::config::GlobalConfig::default();
+
+        let author: Author = toml::from_str(r#"mismatch_symbol = "*""#).unwrap();
+        let out = crate::config::rendered(|io| author.print(io, &data, &global, &mut None));
+        assert_eq!(out, "\u{1b}[0m\u{1b}[33mJane Doe*");
+
+        let committer = Committer {
+            show: Show::Email,
+            mismatch_symbol: Some("*".to_string()),
+            ..Default::default()
+        };
+        let out = crate::config::rendered(|io| committer.print(io, &data, &global, &mut None));
+        assert_eq!(out, "\u{1b}[0m\u{1b}[33mjohn@example.com*");
+
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                committer: data.commit.author.clone(),
+                ..data.commit
+            },
+            ..Default::default()
+        };
+        let out = crate::config::rendered(|io| committer.print(io, &data, &global, &mut None));
+        assert_eq!(out, "\u{1b}[0m\u{1b}[33mjane@example.com");
+    }
+}
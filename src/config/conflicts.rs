@@ -0,0 +1,221 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+use jj_lib::{
+    repo::Repo,
+    revset::{
+        RevsetFilterPredicate, SymbolResolver, SymbolResolverExtension, UserRevsetExpression,
+    },
+};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the number of conflicted commits in the visible history, or nothing if there are none.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Conflicts {
+    /// Text that will be printed before the number of conflicted commits.
+    #[serde(default = "default_symbol")]
+    symbol: String,
+    /// Only look at commits at most this many generations below the visible heads.
+    #[serde(default = "default_depth")]
+    depth: Option<u64>,
+    /// Maximum number that will be rendered, larger counts are rendered as `{max}+`.
+    /// The history is only walked until this many conflicted commits were found.
+    #[serde(default = "default_max")]
+    max: Option<usize>,
+    /// Controls how the number of conflicted commits is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_symbol() -> String {
+    "✖".to_string()
+}
+
+fn default_depth() -> Option<u64> {
+    Some(1000)
+}
+
+fn default_max() -> Option<usize> {
+    Some(99)
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Red),
+        ..Default::default()
+    }
+}
+
+impl Default for Conflicts {
+    fn default() -> Self {
+        Self {
+            symbol: default_symbol(),
+            depth: default_depth(),
+            max: default_max(),
+            style: default_style(),
+        }
+    }
+}
+
+impl Conflicts {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(conflicts) = data.conflicts.filter(|conflicts| *conflicts != 0) else {
+            return Ok(());
+        };
+
+        self.style.print(io, default_style(), prev_style)?;
+        match self.max {
+            Some(max) if conflicts > max => write!(io, "{}{max}+", self.symbol)?,
+            _ => write!(io, "{}{conflicts}", self.symbol)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.conflicts.is_none() {
+            let repo = state.repo(command_helper)?;
+            data.conflicts = Some(count_conflicts(repo.as_ref(), self.depth, self.max)?);
+        }
+        Ok(())
+    }
+}
+
+/// Counts the conflicted commits at most `depth` generations below the visible heads, stopping
+/// once there are more than `max`.
+fn count_conflicts(
+    repo: &dyn Repo,
+    depth: Option<u64>,
+    max: Option<usize>,
+) -> Result<usize, CommandError> {
+    let heads = UserRevsetExpression::visible_heads();
+    let history = match depth {
+        Some(depth) => heads.ancestors_range(0..depth),
+        None => heads.ancestors(),
+    };
+    let no_extensions: &[Box<dyn SymbolResolverExtension>] = &[];
+    let revset = history
+        .filtered(RevsetFilterPredicate::HasConflict)
+        .resolve_user_expression(repo, &SymbolResolver::new(repo, no_extensions))?
+        .evaluate(repo)?;
+
+    let mut count = 0;
+    for commit_id in revset
+        .iter()
+        .take(max.map_or(usize::MAX, |max| max.saturating_add(1)))
+    {
+        commit_id?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use jj_lib::{
+        backend::{CopyId, TreeValue},
+        merge::Merge,
+        merged_tree::MergedTreeBuilder,
+        repo::ReadonlyRepo,
+        repo_path::RepoPathBuf,
+        settings::UserSettings,
+        workspace::Workspace,
+    };
+    use pollster::FutureExt as _;
+
+    use super::*;
+
+    fn file(repo: &ReadonlyRepo, contents: &str) -> Option<TreeValue> {
+        let path = RepoPathBuf::from_internal_string("file").unwrap();
+        let id = repo
+            .store()
+            .write_file(&path, &mut contents.as_bytes())
+            .block_on()
+            .unwrap();
+        Some(TreeValue::File {
+            id,
+            executable: false,
+            copy_id: CopyId::placeholder(),
+        })
+    }
+
+    fn render(conflicts: &Conflicts, count: usize) -> String {
+        let data = crate::JJData {
+            conflicts: Some(count),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            conflicts.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn counts_conflicted_ancestors() {
+        let settings =
+            UserSettings::from_config(jj_lib::config::StackedConfig::with_defaults()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (_workspace, repo) = Workspace::init_internal_git(&settings, dir.path()).unwrap();
+        let store = repo.store().clone();
+        let mut tx = repo.start_transaction();
+
+        // A conflicted commit with a resolved child on top.
+        let path = RepoPathBuf::from_internal_string("file").unwrap();
+        let mut builder = MergedTreeBuilder::new(store.empty_merged_tree_id());
+        builder.set_or_remove(
+            path,
+            Merge::from_removes_adds(
+                vec![file(&repo, "base\n")],
+                vec![file(&repo, "left\n"), file(&repo, "right\n")],
+            ),
+        );
+        let conflicted_tree = builder.write_tree(&store).unwrap();
+        let conflicted = tx
+            .repo_mut()
+            .new_commit(vec![store.root_commit_id().clone()], conflicted_tree)
+            .write()
+            .unwrap();
+        tx.repo_mut()
+            .new_commit(vec![conflicted.id().clone()], store.empty_merged_tree_id())
+            .write()
+            .unwrap();
+        let repo = tx.commit("create conflicts").unwrap();
+
+        assert!(conflicted.has_conflict());
+        assert_eq!(count_conflicts(repo.as_ref(), None, None).unwrap(), 1);
+        assert_eq!(count_conflicts(repo.as_ref(), Some(1), None).unwrap(), 0);
+        assert_eq!(count_conflicts(repo.as_ref(), Some(2), None).unwrap(), 1);
+        assert_eq!(count_conflicts(repo.as_ref(), None, Some(0)).unwrap(), 1);
+    }
+
+    #[test]
+    fn renders_count() {
+        let conflicts = Conflicts {
+            max: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(render(&conflicts, 1), "\u{1b}[0m\u{1b}[31m✖1");
+        assert_eq!(render(&conflicts, 3), "\u{1b}[0m\u{1b}[31m✖2+");
+        assert_eq!(render(&conflicts, 0), "");
+    }
+}
@@ -0,0 +1,210 @@
+use std::{io::Write, time::SystemTime};
+
+use jj_cli::command_error::CommandError;
+use jj_lib::{backend::MillisSinceEpoch, op_store::OperationMetadata, op_walk};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the time since the last `jj git fetch` according to the operation log.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FetchAge {
+    /// Text that will be printed before the elapsed time.
+    #[serde(default = "default_symbol")]
+    symbol: String,
+    /// Number of seconds after which the last fetch is considered stale.
+    #[serde(default = "default_stale_after")]
+    stale_after: u64,
+    /// Maximum number of operations that will be searched for a fetch.
+    #[serde(default = "default_max_operations")]
+    max_operations: usize,
+    /// Controls how the elapsed time is rendered.
+    #[serde(flatten)]
+    style: Style,
+    /// Controls how the elapsed time is rendered once the last fetch is stale.
+    #[serde(default = "default_stale_style")]
+    stale: Style,
+}
+
+fn default_symbol() -> String {
+    "⇣".to_string()
+}
+
+fn default_stale_after() -> u64 {
+    60 * 60 * 24
+}
+
+fn default_max_operations() -> usize {
+    500
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+fn default_stale_style() -> Style {
+    Style {
+        color: Some(Color::Yellow),
+        ..Default::default()
+    }
+}
+
+impl Default for FetchAge {
+    fn default() -> Self {
+        Self {
+            symbol: default_symbol(),
+            stale_after: default_stale_after(),
+            max_operations: default_max_operations(),
+            style: default_style(),
+            stale: default_stale_style(),
+        }
+    }
+}
+
+impl FetchAge {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        self.print_at(io, data, MillisSinceEpoch(now), prev_style)
+    }
+
+    fn print_at(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        now: MillisSinceEpoch,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(Some(last_fetch)) = data.last_fetch else {
+            return Ok(());
+        };
+
+        let elapsed = (now.0 - last_fetch.0).max(0) as u64 / 1000;
+        if elapsed >= self.stale_after {
+            self.stale.print(
+                io,
+                self.style.merge_with_fallback(Some(default_stale_style())),
+                prev_style,
+            )?;
+        } else {
+            self.style.print(io, default_style(), prev_style)?;
+        }
+        write!(io, "{}{}", self.symbol, format_elapsed(elapsed))?;
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.last_fetch.is_some() {
+            return Ok(());
+        }
+        let repo = state.repo(command_helper)?;
+
+        let mut last_fetch = None;
+        for op in op_walk::walk_ancestors(std::slice::from_ref(repo.operation()))
+            .take(self.max_operations)
+        {
+            let op = op?;
+            if is_fetch(op.metadata()) {
+                last_fetch = Some(op.metadata().time.end.timestamp);
+                break;
+            }
+        }
+        data.last_fetch = Some(last_fetch);
+        Ok(())
+    }
+}
+
+/// Whether the operation was created by `jj git fetch` or `jj git clone`.
+fn is_fetch(metadata: &OperationMetadata) -> bool {
+    metadata.description.starts_with("fetch from git remote")
+}
+
+/// Formats a number of seconds using its largest unit, e.g. `3h`.
+pub(super) fn format_elapsed(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jj_lib::{
+        backend::Timestamp,
+        op_store::{OperationMetadata, TimestampRange},
+    };
+
+    use super::*;
+
+    fn operation(description: &str, millis: i64) -> OperationMetadata {
+        let timestamp = Timestamp {
+            timestamp: MillisSinceEpoch(millis),
+            tz_offset: 0,
+        };
+        OperationMetadata {
+            time: TimestampRange {
+                start: timestamp,
+                end: timestamp,
+            },
+            description: description.to_string(),
+            hostname: Default::default(),
+            username: Default::default(),
+            is_snapshot: false,
+            tags: Default::default(),
+        }
+    }
+
+    fn render(config: &FetchAge, op: &OperationMetadata, now: i64) -> String {
+        let data = crate::JJData {
+            last_fetch: Some(is_fetch(op).then_some(op.time.end.timestamp)),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| config.print_at(io, &data, MillisSinceEpoch(now), &mut None))
+    }
+
+    #[test]
+    fn renders_time_since_fetch() {
+        let op = operation("fetch from git remote(s) origin", 0);
+        assert_eq!(
+            render(&FetchAge::default(), &op, 3 * 60 * 60 * 1000),
+            "\u{1b}[0m\u{1b}[90m⇣3h"
+        );
+    }
+
+    #[test]
+    fn stale_fetch_uses_stale_style() {
+        let op = operation("fetch from git remote(s) origin", 0);
+        assert_eq!(
+            render(&FetchAge::default(), &op, 2 * 24 * 60 * 60 * 1000),
+            "\u{1b}[0m\u{1b}[33m⇣2d"
+        );
+    }
+
+    #[test]
+    fn ignores_other_operations() {
+        let op = operation("snapshot working copy", 0);
+        assert_eq!(render(&FetchAge::default(), &op, 1000), "");
+    }
+}
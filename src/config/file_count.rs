@@ -0,0 +1,138 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the number of files in the working copy's tree.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FileCount {
+    /// Text that will be printed before the number of files.
+    #[serde(default = "default_symbol")]
+    symbol: String,
+    /// Maximum number that will be rendered, larger counts are rendered as `{max}+`.
+    /// The tree is only walked until this many files were found.
+    #[serde(default = "default_max")]
+    max: Option<usize>,
+    /// Controls how the number of files is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_symbol() -> String {
+    "󰈙 ".to_string()
+}
+
+fn default_max() -> Option<usize> {
+    Some(9999)
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl Default for FileCount {
+    fn default() -> Self {
+        Self {
+            symbol: default_symbol(),
+            max: default_max(),
+            style: default_style(),
+        }
+    }
+}
+
+impl FileCount {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(Some(files)) = data.file_count else {
+            return Ok(());
+        };
+
+        self.style.print(io, default_style(), prev_style)?;
+        match self.max {
+            Some(max) if files > max => write!(io, "{}{max}+", self.symbol)?,
+            _ => write!(io, "{}{files}", self.symbol)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.file_count.is_some() {
+            return Ok(());
+        }
+        let Some(tree) = state.tree(command_helper)? else {
+            data.file_count = Some(None);
+            return Ok(());
+        };
+        data.file_count = Some(Some(count_files(
+            tree.entries().map(|(path, _value)| path),
+            self.max,
+        )));
+        Ok(())
+    }
+}
+
+/// Counts `entries`, stopping once there are more than `max`.
+fn count_files<T>(entries: impl Iterator<Item = T>, max: Option<usize>) -> usize {
+    match max {
+        Some(max) => entries.take(max.saturating_add(1)).count(),
+        None => entries.count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(file_count: &FileCount, files: Option<usize>) -> String {
+        let data = crate::JJData {
+            file_count: Some(files),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            file_count.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn counts_tree_entries() {
+        let tree = ["Cargo.toml", "README.md", "src/main.rs"];
+        assert_eq!(count_files(tree.iter(), None), 3);
+        assert_eq!(count_files(tree.iter(), Some(1)), 2);
+    }
+
+    #[test]
+    fn renders_file_count() {
+        let file_count = FileCount {
+            symbol: String::new(),
+            max: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(render(&file_count, Some(2)), "\u{1b}[0m\u{1b}[90m2");
+        assert_eq!(render(&file_count, Some(3)), "\u{1b}[0m\u{1b}[90m2+");
+        assert_eq!(render(&file_count, None), "");
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::util::{Color, Style};
+
+/// Renders a single module, or just a text, instead of the whole prompt while the working copy is
+/// in a given state.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Focus {
+    /// The state the working copy has to be in for the prompt to be focused.
+    when: FocusOn,
+    /// Type of the module that is rendered alone, e.g. `State`. The first module of that type is used.
+    #[serde(default, deserialize_with = "module_type")]
+    module: Option<String>,
+    /// Text that will be printed when no `module` is set or no module of that type is configured.
+    #[serde(default)]
+    text: Option<String>,
+    /// Controls how the `text` is rendered.
+    #[serde(flatten, default = "default_style")]
+    style: Style,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusOn {
+    /// Conflict => The working copy contains conflicts
+    Conflict,
+    /// Divergent => The working copy's change is divergent
+    Divergent,
+    /// Hidden => The working copy commit is hidden
+    Hidden,
+    /// Immutable => The working copy commit is immutable
+    Immutable,
+    /// Empty => The working copy commit is empty
+    Empty,
+}
+
+/// Only accepts the `type` of a module, so a typo doesn't silently fall back to the `text`.
+fn module_type<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let Some(kind) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let mut module = toml::Table::new();
+    module.insert("type".to_string(), toml::Value::String(kind.clone()));
+    toml::Value::Table(module)
+        .try_into::<super::ModuleConfig>()
+        .map_err(serde::de::Error::custom)?;
+    Ok(Some(kind))
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Red),
+        ..Default::default()
+    }
+}
+
+impl FocusOn {
+    fn is_active(self, warnings: &crate::CommitWarnings) -> bool {
+        let active = match self {
+            FocusOn::Conflict => warnings.conflict,
+            FocusOn::Divergent => warnings.divergent,
+            FocusOn::Hidden => warnings.hidden,
+            FocusOn::Immutable => warnings.immutable,
+            FocusOn::Empty => warnings.empty,
+        };
+        active == Some(true)
+    }
+}
+
+impl Focus {
+    /// The type of the module that is rendered alone, if any.
+    pub(super) fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+
+    pub(super) fn print_text(
+        &self,
+        io: &mut impl Write,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        self.style.print(io, default_style(), prev_style)?;
+        write!(io, "{text}")?;
+        Ok(())
+    }
+}
+
+/// The first entry of `focus_on` whose state is active.
+pub(super) fn active<'a>(focus_on: &'a [Focus], data: &crate::JJData) -> Option<&'a Focus> {
+    focus_on
+        .iter()
+        .find(|focus| focus.when.is_active(&data.commit.warnings))
+}
+
+/// Detects the states `focus_on` reacts to, so the focus is known before any module renders.
+pub(super) fn parse(
+    focus_on: &[Focus],
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+    global: &super::GlobalConfig,
+) -> Result<(), CommandError> {
+    if focus_on.is_empty() {
+        return Ok(());
+    }
+    let states: Vec<FocusOn> = focus_on.iter().map(|focus| focus.when).collect();
+    super::state::State::detecting(&states).parse(command_helper, state, data, global)
+}
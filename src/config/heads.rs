@@ -0,0 +1,135 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the number of visible heads in the repo.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Heads {
+    /// Text that will be printed before the number of heads.
+    #[serde(default = "default_symbol")]
+    symbol: String,
+    /// Text that will be printed when there is only a single head.
+    #[serde(default)]
+    clean_symbol: Option<String>,
+    /// Maximum number that will be rendered, larger counts are rendered as `{max}+`.
+    #[serde(default = "default_max")]
+    max: Option<usize>,
+    /// Controls how the number of heads is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_symbol() -> String {
+    "".to_string()
+}
+
+fn default_max() -> Option<usize> {
+    Some(99)
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Yellow),
+        ..Default::default()
+    }
+}
+
+impl Default for Heads {
+    fn default() -> Self {
+        Self {
+            symbol: default_symbol(),
+            clean_symbol: None,
+            max: default_max(),
+            style: default_style(),
+        }
+    }
+}
+
+impl Heads {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(heads) = data.heads else {
+            return Ok(());
+        };
+
+        if heads <= 1 {
+            if let Some(clean_symbol) = &self.clean_symbol {
+                self.style.print(io, default_style(), prev_style)?;
+                write!(io, "{clean_symbol}")?;
+            }
+            return Ok(());
+        }
+
+        self.style.print(io, default_style(), prev_style)?;
+        match self.max {
+            Some(max) if heads > max => write!(io, "{}{max}+", self.symbol)?,
+            _ => write!(io, "{}{heads}", self.symbol)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.heads.is_some() {
+            return Ok(());
+        }
+        let repo = state.repo(command_helper)?;
+        data.heads = Some(repo.view().heads().len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(heads: &Heads, count: usize) -> String {
+        let data = crate::JJData {
+            heads: Some(count),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            heads.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn renders_head_count() {
+        assert_eq!(render(&Heads::default(), 3), "\u{1b}[0m\u{1b}[33m3");
+    }
+
+    #[test]
+    fn renders_nothing_for_single_head() {
+        assert_eq!(render(&Heads::default(), 1), "");
+    }
+
+    #[test]
+    fn caps_head_count() {
+        let heads = Heads {
+            max: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(render(&heads, 3), "\u{1b}[0m\u{1b}[33m2+");
+    }
+}
@@ -0,0 +1,205 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints a hint suggesting the next jj command based on the working copy's state.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Hint {
+    /// Rules that are checked in order. The first matching rule is rendered.
+    #[serde(default = "default_rules")]
+    rules: Vec<HintRule>,
+    /// Controls how the hint is rendered unless a rule overrides it.
+    #[serde(flatten)]
+    style: Style,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+struct HintRule {
+    /// The state the working copy has to be in for this rule to match.
+    when: HintCondition,
+    /// The text that will be printed when this rule matches.
+    text: String,
+    /// Controls how the hint is rendered when this rule matches.
+    #[serde(flatten, default)]
+    style: Style,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum HintCondition {
+    /// Conflict => The working copy contains conflicts
+    Conflict,
+    /// MissingDescription => The working copy has changes but no description
+    MissingDescription,
+    /// Described => The working copy has changes and a description
+    Described,
+    /// EmptyWorkingCopy => The working copy has neither changes nor a description
+    EmptyWorkingCopy,
+}
+
+impl HintCondition {
+    fn matches(self, data: &crate::JJData) -> bool {
+        let warnings = &data.commit.warnings;
+        let (described, empty) = (data.commit.described, warnings.empty);
+        match self {
+            HintCondition::Conflict => warnings.conflict == Some(true),
+            HintCondition::MissingDescription => described == Some(false) && empty == Some(false),
+            HintCondition::Described => described == Some(true) && empty == Some(false),
+            HintCondition::EmptyWorkingCopy => described == Some(false) && empty == Some(true),
+        }
+    }
+}
+
+/// Detects the warnings the rules are checked against, whichever ones the `State` module shows.
+pub(super) fn detector() -> super::state::State {
+    use super::focus::FocusOn;
+
+    super::state::State::detecting(&[FocusOn::Conflict, FocusOn::Empty])
+}
+
+fn default_rules() -> Vec<HintRule> {
+    vec![
+        HintRule {
+            when: HintCondition::Conflict,
+            text: "→ jj resolve".to_string(),
+            style: Style {
+                color: Some(Color::Red),
+                ..Default::default()
+            },
+        },
+        HintRule {
+            when: HintCondition::MissingDescription,
+            text: "→ jj describe".to_string(),
+            style: Default::default(),
+        },
+        HintRule {
+            when: HintCondition::Described,
+            text: "→ jj new".to_string(),
+            style: Default::default(),
+        },
+    ]
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl Default for Hint {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            style: default_style(),
+        }
+    }
+}
+
+impl Hint {
+    fn active_rule(&self, data: &crate::JJData) -> Option<&HintRule> {
+        self.rules.iter().find(|rule| rule.when.matches(data))
+    }
+
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(rule) = self.active_rule(data) else {
+            return Ok(());
+        };
+
+        rule.style.print(
+            io,
+            self.style.merge_with_fallback(Some(default_style())),
+            prev_style,
+        )?;
+        write!(io, "{}", rule.text)?;
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        super::commit::resolve_described(command_helper, state, data)?;
+        detector().parse(command_helper, state, data, global)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(desc: &str, empty: bool, conflict: bool) -> crate::JJData {
+        crate::JJData {
+            commit: crate::CommitData {
+                described: Some(!desc.is_empty()),
+                warnings: crate::CommitWarnings {
+                    empty: Some(empty),
+                    conflict: Some(conflict),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn rule(when: HintCondition, text: &str) -> HintRule {
+        HintRule {
+            when,
+            text: text.to_string(),
+            style: Default::default(),
+        }
+    }
+
+    fn hint(data: &crate::JJData) -> Option<String> {
+        let hint = Hint {
+            rules: vec![
+                rule(HintCondition::Conflict, "resolve"),
+                rule(HintCondition::MissingDescription, "describe"),
+                rule(HintCondition::Described, "new"),
+                rule(HintCondition::EmptyWorkingCopy, "empty"),
+            ],
+            style: Default::default(),
+        };
+        hint.active_rule(data).map(|rule| rule.text.clone())
+    }
+
+    #[test]
+    fn conflict_takes_precedence() {
+        assert_eq!(hint(&data("", false, true)), Some("resolve".to_string()));
+    }
+
+    #[test]
+    fn missing_description() {
+        assert_eq!(hint(&data("", false, false)), Some("describe".to_string()));
+    }
+
+    #[test]
+    fn described() {
+        assert_eq!(
+            hint(&data("fix things", false, false)),
+            Some("new".to_string())
+        );
+    }
+
+    #[test]
+    fn empty_working_copy() {
+        assert_eq!(hint(&data("", true, false)), Some("empty".to_string()));
+    }
+}
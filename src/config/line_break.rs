@@ -0,0 +1,9 @@
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Starts a new line, so the modules after it are rendered below the ones before it. Separators
+/// and styles don't carry over to the new line.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct LineBreak {}
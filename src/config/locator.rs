@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the shortest way to name the working copy relative to a bookmark or `trunk()`, e.g.
+/// `main`, `feature+2` or `trunk+3`, and `@` if neither applies.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Locator {
+    /// Text used for `trunk()` in the locator.
+    #[serde(default = "default_trunk_text")]
+    trunk_text: String,
+    /// Controls how the locator is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_trunk_text() -> String {
+    "trunk".to_string()
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Magenta),
+        ..Default::default()
+    }
+}
+
+impl Default for Locator {
+    fn default() -> Self {
+        Self {
+            trunk_text: default_trunk_text(),
+            style: default_style(),
+        }
+    }
+}
+
+fn with_offset(name: &str, distance: usize) -> String {
+    match distance {
+        0 => name.to_string(),
+        distance => format!("{name}+{distance}"),
+    }
+}
+
+impl Locator {
+    /// The most compact locator, preferring bookmarks over trunk when both are equally long.
+    fn locator(&self, data: &crate::JJData) -> String {
+        let bookmark = data
+            .bookmarks
+            .bookmarks
+            .iter()
+            .flatten()
+            .filter(|bookmark| !bookmark.ahead)
+            .map(|bookmark| with_offset(&bookmark.name, bookmark.distance));
+        let trunk = data
+            .trunk_distance
+            .flatten()
+            .map(|distance| with_offset(&self.trunk_text, distance));
+        bookmark
+            .chain(trunk)
+            .min_by_key(|locator| super::util::visible_width(locator))
+            .unwrap_or_else(|| "@".to_string())
+    }
+
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        self.style.print(io, default_style(), prev_style)?;
+        write!(io, "{}", global.escape.apply(&self.locator(data)))?;
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        super::bookmarks::Bookmarks::default().parse(command_helper, state, data, global)?;
+        if data.trunk_distance.is_none() {
+            // Without a trunk bookmark `trunk()` is the root commit, which isn't worth naming.
+            let count = state.count_revset(command_helper, "(trunk() ~ root())::@")?;
+            data.trunk_distance = Some(count.checked_sub(1));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::bookmark;
+
+    fn render(bookmarks: Vec<crate::Bookmark>, trunk_distance: Option<usize>) -> String {
+        let data = crate::JJData {
+            bookmarks: crate::BookmarkData {
+                bookmarks: Some(bookmarks),
+                ..Default::default()
+            },
+            trunk_distance: Some(trunk_distance),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            Locator::default().print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn picks_most_compact_locator() {
+        assert_eq!(
+            render(vec![bookmark("stack", 2)], Some(5)),
+            "\u{1b}[0m\u{1b}[35mstack+2"
+        );
+        assert_eq!(
+            render(vec![bookmark("feature/parser", 1)], Some(3)),
+            "\u{1b}[0m\u{1b}[35mtrunk+3"
+        );
+        assert_eq!(
+            render(vec![bookmark("feature/parser", 0)], Some(0)),
+            "\u{1b}[0m\u{1b}[35mtrunk"
+        );
+        assert_eq!(render(Vec::new(), None), "\u{1b}[0m\u{1b}[35m@");
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::{collections::BTreeMap, io::Write};
 
 use jj_cli::command_error::CommandError;
 #[cfg(feature = "json-schema")]
@@ -11,7 +11,7 @@ use super::util::{Color, Style};
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Metrics {
-    /// Controls how the changes are rendered. Use {added}, {removed} and {changed} to render the number of changes.
+    /// Controls how the changes are rendered. Use {added}, {removed}, {changed} and {net} to render the number of changes.
     #[serde(default = "default_template")]
     template: String,
 
@@ -19,8 +19,52 @@ pub struct Metrics {
     #[serde(default)]
     hide_if_empty: bool,
 
+    /// Hide the module, like a clean working copy, while fewer than this many lines are added and
+    /// removed in total. If `min_files` is set too, the diff has to be below both.
+    #[serde(default)]
+    min_lines: Option<usize>,
+
+    /// Hide the module, like a clean working copy, while fewer than this many files are changed.
+    /// If `min_lines` is set too, the diff has to be below both.
+    #[serde(default)]
+    min_files: Option<usize>,
+
+    /// Render added and removed lines as a single `+N/-M` segment instead of using the template.
+    #[serde(default)]
+    combined_add_remove: bool,
+
+    /// Text rendered between added and removed lines when `combined_add_remove` is set.
+    #[serde(default = "default_glue")]
+    glue: String,
+
+    /// Detect renamed and copied files. Disabling this speeds up commits with many renames,
+    /// but a rename is then counted as a removed and an added file.
+    #[serde(default = "default_rename_detection")]
+    rename_detection: bool,
+
+    /// The commit the working copy is compared against.
+    #[serde(default)]
+    base: DiffBase,
+
+    /// Render the segments in this order, separated by spaces, instead of using the template.
+    /// Segments that aren't listed follow in their default order, except for `Net`.
+    #[serde(default)]
+    order: Vec<MetricField>,
+
     // added_files: Style,
     // removed_files: Style,
+    /// Render a bar like `[++++--]` showing the ratio of added to removed lines after the numbers.
+    #[serde(default)]
+    bar: Option<Bar>,
+
+    /// Render the file with the most added and removed lines after the numbers, e.g. `main.rs`.
+    #[serde(default)]
+    largest_file: Option<LargestFile>,
+
+    /// Only render the `bar`, not the numbers.
+    #[serde(default)]
+    hide_numbers: bool,
+
     /// Controls how the number of changed files is rendered.
     #[serde(default = "default_changed_files")]
     changed_files: Metric,
@@ -41,6 +85,16 @@ impl Default for Metrics {
     fn default() -> Self {
         Self {
             hide_if_empty: false,
+            min_lines: None,
+            min_files: None,
+            combined_add_remove: false,
+            glue: default_glue(),
+            rename_detection: default_rename_detection(),
+            base: Default::default(),
+            order: Vec::new(),
+            bar: None,
+            largest_file: None,
+            hide_numbers: false,
             style: default_style(),
             template: default_template(),
             changed_files: default_changed_files(),
@@ -50,6 +104,48 @@ impl Default for Metrics {
     }
 }
 
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DiffBase {
+    /// Parent => [default] Only count the changes in the working copy commit
+    #[default]
+    Parent,
+    /// Bookmark => Count all changes since the nearest ancestor bookmark, falling back to the parent
+    /// if there is none
+    Bookmark,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricField {
+    /// The number of changed files
+    Changed,
+    /// The number of added lines
+    Added,
+    /// The number of removed lines
+    Removed,
+    /// The number of added minus removed lines, rendered like the added lines if it's positive and
+    /// like the removed lines if it's negative. Only rendered if listed.
+    Net,
+}
+
+const DEFAULT_ORDER: [MetricField; 3] = [
+    MetricField::Changed,
+    MetricField::Added,
+    MetricField::Removed,
+];
+
+/// `order` followed by the fields it doesn't list, in their default order.
+fn complete_order(order: &[MetricField]) -> Vec<MetricField> {
+    let mut complete = Vec::with_capacity(DEFAULT_ORDER.len());
+    for field in order.iter().chain(DEFAULT_ORDER.iter()) {
+        if !complete.contains(field) {
+            complete.push(*field);
+        }
+    }
+    complete
+}
+
 fn default_removed_lines() -> Metric {
     Metric {
         style: default_removed_style(),
@@ -98,6 +194,14 @@ fn default_template() -> String {
     "[{changed} {added}{removed}]".to_string()
 }
 
+fn default_glue() -> String {
+    "/".to_string()
+}
+
+fn default_rename_detection() -> bool {
+    true
+}
+
 fn default_style() -> Style {
     Style {
         color: Some(Color::Magenta),
@@ -112,6 +216,20 @@ struct Metric {
     prefix: String,
     #[serde(default)]
     suffix: String,
+    /// Text rendered in place of `prefix`, e.g. an arrow, with its own `symbol_style`.
+    #[serde(default)]
+    symbol: Option<String>,
+    /// Controls how the `symbol` is rendered, defaults to the style of the number.
+    #[serde(default)]
+    symbol_style: Option<Style>,
+    /// Controls how the number is rendered when it is zero, e.g. dimmed.
+    #[serde(default)]
+    zero_style: Option<Style>,
+    /// Styles applied once the number reaches a cutoff, e.g. `{ "500" = { color = "Red" } }`.
+    /// The highest reached cutoff wins, keys it doesn't set fall back to the metric's style.
+    #[serde(default)]
+    #[cfg_attr(feature = "json-schema", schemars(with = "BTreeMap<String, Style>"))]
+    thresholds: Thresholds,
     #[serde(flatten)]
     style: Style,
 }
@@ -123,36 +241,330 @@ impl Metric {
         fallback: impl Into<Option<Style>>,
         prev_style: &mut Option<nu_ansi_term::Style>,
     ) -> String {
+        let style = match &self.zero_style {
+            Some(zero_style) if number == 0 => zero_style.clone(),
+            _ => match self.thresholds.0.range(..=number).next_back() {
+                Some((_, threshold)) => threshold.merge_with_fallback(Some(self.style.clone())),
+                None => self.style.clone(),
+            },
+        };
+        let style = style.merge_with_fallback(fallback.into());
+        let prefix = match &self.symbol {
+            Some(symbol) => match &self.symbol_style {
+                Some(symbol_style) => format!(
+                    "{}{symbol}{}",
+                    symbol_style.format(style.clone(), prev_style),
+                    style.format(None, prev_style),
+                ),
+                None => format!("{}{symbol}", style.format(None, prev_style)),
+            },
+            None => format!("{}{}", style.format(None, prev_style), self.prefix),
+        };
         format!(
-            "{}{}{}{}{}",
-            self.style.format(fallback, prev_style),
-            self.prefix,
-            number,
+            "{prefix}{number}{}{}",
             self.suffix,
             global_style.format(default_style(), prev_style),
         )
     }
 }
 
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+struct Bar {
+    /// Number of characters the added and removed lines are split across.
+    #[serde(default = "default_bar_width")]
+    width: usize,
+    /// Character repeated for the share of added lines.
+    #[serde(default = "default_bar_added")]
+    added: char,
+    /// Character repeated for the share of removed lines.
+    #[serde(default = "default_bar_removed")]
+    removed: char,
+    /// Text that will be printed before the bar.
+    #[serde(default = "default_bar_prefix")]
+    prefix: String,
+    /// Text that will be printed after the bar.
+    #[serde(default = "default_bar_suffix")]
+    suffix: String,
+}
+
+fn default_bar_width() -> usize {
+    6
+}
+
+fn default_bar_added() -> char {
+    '+'
+}
+
+fn default_bar_removed() -> char {
+    '-'
+}
+
+fn default_bar_prefix() -> String {
+    "[".to_string()
+}
+
+fn default_bar_suffix() -> String {
+    "]".to_string()
+}
+
+impl Bar {
+    /// How many of the `width` characters go to added and removed lines. Each side that has
+    /// changes gets at least one character if there is room for both.
+    fn split(&self, added: usize, removed: usize) -> (usize, usize) {
+        let total = added + removed;
+        if total == 0 || self.width == 0 {
+            return (0, 0);
+        }
+        let mut added_width = (self.width * added + total / 2) / total;
+        if added > 0 {
+            added_width = added_width.max(1);
+        }
+        if removed > 0 && self.width > 1 {
+            added_width = added_width.min(self.width - 1);
+        }
+        (added_width, self.width - added_width)
+    }
+
+    fn print(
+        &self,
+        io: &mut impl Write,
+        diff: &crate::CommitDiff,
+        metrics: &Metrics,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let (added, removed) = self.split(diff.lines_added, diff.lines_removed);
+        metrics.style.print(io, default_style(), prev_style)?;
+        write!(io, "{}", self.prefix)?;
+        metrics
+            .added_lines
+            .style
+            .print(io, default_added_style(), prev_style)?;
+        write!(io, "{}", self.added.to_string().repeat(added))?;
+        metrics
+            .removed_lines
+            .style
+            .print(io, default_removed_style(), prev_style)?;
+        write!(io, "{}", self.removed.to_string().repeat(removed))?;
+        metrics.style.print(io, default_style(), prev_style)?;
+        write!(io, "{}", self.suffix)?;
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct LargestFile {
+    /// Also render the share of the file's lines that were added or changed, e.g. `main.rs 30%`.
+    /// Binary, added and deleted files have no percentage.
+    #[serde(default)]
+    percent: bool,
+    /// Controls how the file is rendered, defaults to the style of the module.
+    #[serde(flatten)]
+    style: Style,
+}
+
+impl LargestFile {
+    fn print(
+        &self,
+        io: &mut impl Write,
+        file: &crate::LargestFileDiff,
+        metrics: &Metrics,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let style = self.style.merge_with_fallback(Some(metrics.style.clone()));
+        style.print(io, default_style(), prev_style)?;
+        let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+        write!(io, "{name}")?;
+        if self.percent
+            && let Some(percent) = file.percent
+        {
+            write!(io, " {percent}%")?;
+        }
+        Ok(())
+    }
+
+    /// The file in `stats` with the most added and removed lines, the first one by path on ties.
+    fn collect(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        stats: &jj_cli::diff_util::DiffStats,
+    ) -> Result<Option<crate::LargestFileDiff>, CommandError> {
+        let Some(entry) = stats.entries().iter().min_by(|a, b| {
+            let changed = |entry: &jj_cli::diff_util::DiffStatEntry| {
+                entry
+                    .added_removed
+                    .map_or(0, |(added, removed)| added + removed)
+            };
+            changed(b)
+                .cmp(&changed(a))
+                .then_with(|| a.path.target().cmp(b.path.target()))
+        }) else {
+            return Ok(None);
+        };
+        let path = entry.path.target();
+        let percent = match entry.added_removed {
+            Some((added, removed)) if self.percent => state
+                .line_count(command_helper, path)?
+                .and_then(|lines| percent_changed(added, removed, lines)),
+            _ => None,
+        };
+        Ok(Some(crate::LargestFileDiff {
+            path: path.as_internal_file_string().to_string(),
+            percent,
+        }))
+    }
+}
+
+/// Share of a file's `lines` that were added or changed, rounded. `None` if every line is new,
+/// e.g. because the file was added.
+fn percent_changed(added: usize, removed: usize, lines: usize) -> Option<usize> {
+    if lines == 0 || removed == 0 && added >= lines {
+        return None;
+    }
+    Some(((added * 100 + lines / 2) / lines).min(100))
+}
+
+/// Styles keyed by the cutoff they apply from. TOML keys are always strings, so the cutoffs are
+/// parsed from them.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(try_from = "BTreeMap<String, Style>", into = "BTreeMap<String, Style>")]
+struct Thresholds(BTreeMap<usize, Style>);
+
+impl TryFrom<BTreeMap<String, Style>> for Thresholds {
+    type Error = String;
+
+    fn try_from(thresholds: BTreeMap<String, Style>) -> Result<Self, Self::Error> {
+        thresholds
+            .into_iter()
+            .map(|(cutoff, style)| match cutoff.parse() {
+                Ok(cutoff) => Ok((cutoff, style)),
+                Err(_) => Err(format!("invalid threshold `{cutoff}`, expected a number")),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl From<Thresholds> for BTreeMap<String, Style> {
+    fn from(thresholds: Thresholds) -> Self {
+        thresholds
+            .0
+            .into_iter()
+            .map(|(cutoff, style)| (cutoff.to_string(), style))
+            .collect()
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct Context {
     added: String,
     removed: String,
     changed: String,
+    net: String,
 }
 
 impl Metrics {
+    /// Whether `diff` is below every configured minimum, `false` if there is none.
+    fn is_below_minimum(&self, diff: &crate::CommitDiff) -> bool {
+        let lines = self
+            .min_lines
+            .map(|min| diff.lines_added + diff.lines_removed < min);
+        let files = self.min_files.map(|min| diff.files_changed < min);
+        match (lines, files) {
+            (None, None) => false,
+            (lines, files) => lines.unwrap_or(true) && files.unwrap_or(true),
+        }
+    }
+
     pub fn print(
         &self,
         io: &mut impl Write,
         data: &crate::JJData,
-        module_separator: &str,
+        _global: &super::GlobalConfig,
         prev_style: &mut Option<nu_ansi_term::Style>,
     ) -> Result<(), CommandError> {
         let Some(diff) = &data.commit.diff else {
             return Ok(());
         };
-        if self.hide_if_empty && diff.is_empty() {
+        // The root commit has nothing to compare against.
+        if data.commit.root == Some(true)
+            || self.hide_if_empty && diff.is_empty()
+            || self.is_below_minimum(diff)
+        {
+            return Ok(());
+        }
+
+        let mut printed = !self.hide_numbers;
+        if !self.hide_numbers {
+            self.print_numbers(io, diff, prev_style)?;
+        }
+        if let Some(bar) = &self.bar
+            && (diff.lines_added != 0 || diff.lines_removed != 0)
+        {
+            if self.hide_numbers {
+                *prev_style = None;
+            } else {
+                write!(io, " ")?;
+            }
+            bar.print(io, diff, self, prev_style)?;
+            printed = true;
+        }
+        if let Some(largest_file) = &self.largest_file
+            && let Some(file) = &diff.largest_file
+        {
+            if printed {
+                write!(io, " ")?;
+            } else {
+                *prev_style = None;
+            }
+            largest_file.print(io, file, self, prev_style)?;
+        }
+        Ok(())
+    }
+
+    /// The added minus the removed lines, styled like whichever of them is larger.
+    fn format_net(&self, diff: &crate::CommitDiff) -> String {
+        match diff.lines_added.checked_sub(diff.lines_removed) {
+            Some(net) => {
+                self.added_lines
+                    .format(net, &self.style, default_added_style(), &mut None)
+            }
+            None => self.removed_lines.format(
+                diff.lines_removed - diff.lines_added,
+                &self.style,
+                default_removed_style(),
+                &mut None,
+            ),
+        }
+    }
+
+    fn print_numbers(
+        &self,
+        io: &mut impl Write,
+        diff: &crate::CommitDiff,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        if self.combined_add_remove {
+            let added = self.added_lines.format(
+                diff.lines_added,
+                &self.style,
+                default_added_style(),
+                &mut None,
+            );
+            let removed = self.removed_lines.format(
+                diff.lines_removed,
+                &self.style,
+                default_removed_style(),
+                &mut None,
+            );
+
+            *prev_style = None;
+            self.style.print(io, default_style(), prev_style)?;
+
+            write!(io, "{added}{}{removed}", self.glue)?;
+
             return Ok(());
         }
 
@@ -175,7 +587,27 @@ impl Metrics {
                 default_changed_style(),
                 &mut None,
             ),
+            net: self.format_net(diff),
         };
+        if !self.order.is_empty() {
+            let segments: Vec<&str> = complete_order(&self.order)
+                .into_iter()
+                .map(|field| match field {
+                    MetricField::Changed => context.changed.as_str(),
+                    MetricField::Added => context.added.as_str(),
+                    MetricField::Removed => context.removed.as_str(),
+                    MetricField::Net => context.net.as_str(),
+                })
+                .collect();
+
+            *prev_style = None;
+            self.style.print(io, default_style(), prev_style)?;
+
+            write!(io, "{}", segments.join(" "))?;
+
+            return Ok(());
+        }
+
         let mut tiny_template = tinytemplate::TinyTemplate::new();
         tiny_template
             .add_template("template", &self.template)
@@ -197,34 +629,338 @@ impl Metrics {
         *prev_style = None;
         self.style.print(io, default_style(), prev_style)?;
 
-        write!(io, "{s}{module_separator}")?;
+        write!(io, "{s}")?;
 
         Ok(())
     }
 
+    /// Starts computing the diff stats in the background if `parse` will need them.
+    pub(crate) fn prefetch(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &crate::JJData,
+    ) -> Result<(), CommandError> {
+        if data.commit.diff.is_some() || self.base != DiffBase::Parent {
+            return Ok(());
+        }
+        state.prefetch_diff_stats(command_helper, self.rename_detection)
+    }
+
     pub(crate) fn parse(
         &self,
         command_helper: &jj_cli::cli_util::CommandHelper,
         state: &mut crate::State,
         data: &mut crate::JJData,
-        _global: &super::GlobalConfig,
+        global: &super::GlobalConfig,
     ) -> Result<(), CommandError> {
         if data.commit.diff.is_some() {
             return Ok(());
         }
+        super::commit::resolve_root(command_helper, state, data)?;
+        if data.commit.root == Some(true) {
+            data.commit.diff = Some(crate::CommitDiff::default());
+            return Ok(());
+        }
 
         let mut diff = crate::CommitDiff::default();
 
-        let Some(stats) = state.diff_stats(command_helper)? else {
+        let base = match self.base {
+            DiffBase::Parent => None,
+            DiffBase::Bookmark => {
+                super::bookmarks::Bookmarks::default().parse(
+                    command_helper,
+                    state,
+                    data,
+                    global,
+                )?;
+                nearest_bookmark(data.bookmarks.bookmarks.as_deref().unwrap_or_default())
+                    .map(|bookmark| bookmark.target.clone())
+            }
+        };
+        let stats = match &base {
+            Some(base) => state.diff_stats_since(command_helper, base, self.rename_detection)?,
+            None => state.diff_stats(command_helper, self.rename_detection)?,
+        };
+        let Some(stats) = stats else {
             return Ok(());
         };
 
         diff.files_changed = stats.entries().len();
         diff.lines_added = stats.count_total_added();
         diff.lines_removed = stats.count_total_removed();
+        if let Some(largest_file) = &self.largest_file {
+            diff.largest_file = largest_file.collect(command_helper, state, &stats)?;
+        }
 
         data.commit.diff = Some(diff);
 
         Ok(())
     }
 }
+
+/// The ancestor bookmark closest to the working copy, which is where the changes are counted from.
+fn nearest_bookmark(bookmarks: &[crate::Bookmark]) -> Option<&crate::Bookmark> {
+    bookmarks
+        .iter()
+        .filter(|bookmark| !bookmark.ahead)
+        .min_by_key(|bookmark| bookmark.distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::bookmark;
+    use insta::assert_snapshot;
+
+    fn data(diff: crate::CommitDiff) -> crate::JJData {
+        crate::JJData {
+            commit: crate::CommitData {
+                diff: Some(diff),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn render(metrics: &Metrics, diff: crate::CommitDiff) -> String {
+        let data = data(diff);
+        crate::config::rendered(|io| {
+            metrics.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn render_combined_add_remove() {
+        let metrics = Metrics {
+            combined_add_remove: true,
+            ..Default::default()
+        };
+        let out = render(
+            &metrics,
+            crate::CommitDiff {
+                files_changed: 2,
+                lines_added: 40,
+                lines_removed: 5,
+                ..Default::default()
+            },
+        );
+
+        assert_snapshot!(format!("{:?}", out));
+    }
+
+    #[test]
+    fn bookmark_base_uses_nearest_bookmark() {
+        // `@` is two commits past `feature`, which is itself three commits past `main`.
+        let bookmarks = [bookmark("main", 5), bookmark("feature", 2)];
+
+        assert_eq!(
+            nearest_bookmark(&bookmarks).map(|bookmark| bookmark.name.as_str()),
+            Some("feature")
+        );
+        assert_eq!(nearest_bookmark(&[]), None);
+    }
+
+    #[test]
+    fn custom_order() {
+        assert_eq!(
+            complete_order(&[MetricField::Removed, MetricField::Added]),
+            [
+                MetricField::Removed,
+                MetricField::Added,
+                MetricField::Changed
+            ]
+        );
+
+        let metrics = Metrics {
+            order: vec![MetricField::Added, MetricField::Removed],
+            ..Default::default()
+        };
+        let out = render(
+            &metrics,
+            crate::CommitDiff {
+                files_changed: 2,
+                lines_added: 40,
+                lines_removed: 5,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            out,
+            "\u{1b}[0m\u{1b}[35m\u{1b}[0m\u{1b}[32m+40\u{1b}[35m \u{1b}[0m\u{1b}[31m-5\u{1b}[35m \u{1b}[0m\u{1b}[36m2\u{1b}[35m"
+        );
+    }
+
+    #[test]
+    fn net_lines() {
+        let metrics = Metrics {
+            order: vec![MetricField::Net],
+            ..Default::default()
+        };
+        let net = |lines_added, lines_removed| {
+            render(
+                &metrics,
+                crate::CommitDiff {
+                    files_changed: 2,
+                    lines_added,
+                    lines_removed,
+                    ..Default::default()
+                },
+            )
+        };
+
+        // The unlisted segments follow in their default order.
+        assert_eq!(
+            net(40, 5),
+            "\u{1b}[0m\u{1b}[35m\u{1b}[0m\u{1b}[32m+35\u{1b}[35m \u{1b}[0m\u{1b}[36m2\u{1b}[35m \u{1b}[0m\u{1b}[32m+40\u{1b}[35m \u{1b}[0m\u{1b}[31m-5\u{1b}[35m"
+        );
+        assert!(net(5, 40).contains("\u{1b}[31m-35"));
+        assert!(net(5, 5).contains("\u{1b}[32m+0"));
+    }
+
+    #[test]
+    fn render_arrows() {
+        let metrics: Metrics = toml::from_str(
+            r#"
+order = ["Added", "Removed", "Changed"]
+
+[added_lines]
+symbol = "↑"
+color = "Green"
+
+[removed_lines]
+symbol = "↓"
+color = "Red"
+zero_style = { color = "BrightBlack" }
+
+[changed_files]
+symbol = "~"
+symbol_style = { color = "BrightBlack" }
+"#,
+        )
+        .unwrap();
+        let removed = |lines_removed| {
+            render(
+                &metrics,
+                crate::CommitDiff {
+                    files_changed: 2,
+                    lines_added: 40,
+                    lines_removed,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let out = removed(5);
+        assert_eq!(
+            crate::config::util::visible_width(&out),
+            "↑40 ↓5 ~2".chars().count()
+        );
+        assert_snapshot!(format!("{:?}\n{:?}", out, removed(0)));
+    }
+
+    #[test]
+    fn highest_reached_threshold_wins() {
+        let metrics: Metrics = toml::from_str(
+            r#"
+template = "{added}"
+
+[added_lines]
+prefix = "+"
+color = "Green"
+thresholds = { "100" = { color = "Yellow" }, "500" = { color = "Red", bold = true } }
+"#,
+        )
+        .unwrap();
+        let added = |lines_added| {
+            render(
+                &metrics,
+                crate::CommitDiff {
+                    lines_added,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_snapshot!(
+            [99, 100, 101, 499, 500, 501]
+                .map(|lines| format!("{lines}: {:?}", added(lines)))
+                .join("\n")
+        );
+    }
+
+    #[test]
+    fn bar_splits_width_by_ratio() {
+        let bar: Bar = toml::from_str("").unwrap();
+        assert_eq!(bar.split(40, 20), (4, 2));
+        assert_eq!(bar.split(1000, 1), (5, 1));
+        assert_eq!(bar.split(0, 7), (0, 6));
+        assert_eq!(bar.split(0, 0), (0, 0));
+
+        let rendered_bar = |metrics: &Metrics, lines_added, lines_removed| {
+            render(
+                metrics,
+                crate::CommitDiff {
+                    files_changed: 1,
+                    lines_added,
+                    lines_removed,
+                    ..Default::default()
+                },
+            )
+        };
+        let bar_only: Metrics = toml::from_str("hide_numbers = true\n[bar]\nwidth = 4").unwrap();
+        let both: Metrics = toml::from_str("template = \"{added}\"\n[bar]").unwrap();
+
+        assert_snapshot!(format!(
+            "{:?}\n{:?}\n{:?}",
+            rendered_bar(&bar_only, 3, 1),
+            rendered_bar(&bar_only, 0, 0),
+            rendered_bar(&both, 40, 20)
+        ));
+    }
+
+    #[test]
+    fn invalid_threshold_is_rejected() {
+        assert!(
+            toml::from_str::<Metric>(r#"thresholds = { "many" = { color = "Red" } }"#).is_err()
+        );
+    }
+
+    #[test]
+    fn unknown_order_field_is_rejected() {
+        assert!(toml::from_str::<Metrics>(r#"order = ["Added", "Total"]"#).is_err());
+    }
+
+    #[test]
+    fn minimums_hide_trivial_diffs() {
+        let shown = |metrics: &Metrics, files_changed, lines_added, lines_removed| {
+            let out = render(
+                metrics,
+                crate::CommitDiff {
+                    files_changed,
+                    lines_added,
+                    lines_removed,
+                    ..Default::default()
+                },
+            );
+            !out.is_empty()
+        };
+        let lines: Metrics = toml::from_str("min_lines = 5").unwrap();
+        assert!(!shown(&lines, 3, 2, 2));
+        assert!(shown(&lines, 1, 3, 2));
+
+        let files: Metrics = toml::from_str("min_files = 2").unwrap();
+        assert!(!shown(&files, 1, 100, 0));
+        assert!(shown(&files, 2, 0, 0));
+
+        let both: Metrics = toml::from_str("min_lines = 5\nmin_files = 2").unwrap();
+        assert!(!shown(&both, 1, 4, 0));
+        assert!(shown(&both, 1, 5, 0));
+        assert!(shown(&both, 2, 0, 0));
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::Write;
+use std::path::Path;
+
+use jj_cli::command_error::CommandError;
+use jj_lib::object_id::ObjectId as _;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A working copy state that triggers a terminal notification.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyOn {
+    Conflict,
+    Divergent,
+}
+
+impl NotifyOn {
+    fn name(self) -> &'static str {
+        match self {
+            NotifyOn::Conflict => "Conflict",
+            NotifyOn::Divergent => "Divergent",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            NotifyOn::Conflict => "jj: working copy has conflicts",
+            NotifyOn::Divergent => "jj: working copy is divergent",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Conflict" => Some(NotifyOn::Conflict),
+            "Divergent" => Some(NotifyOn::Divergent),
+            _ => None,
+        }
+    }
+}
+
+/// Emits an OSC 9 notification for every configured state that became active since the last prompt.
+pub(crate) fn notify(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+    data: &mut crate::JJData,
+    global: &super::GlobalConfig,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    if global.notify_on.is_empty() {
+        return Ok(());
+    }
+
+    let workspace_root = state
+        .workspace_helper(command_helper)?
+        .workspace_root()
+        .to_path_buf();
+    let Some(path) = super::util::cache_file("notify", &workspace_root) else {
+        return Ok(());
+    };
+    let op_id = state.repo(command_helper)?.op_id().hex();
+    notify_changes(&path, &op_id, io, || {
+        if global.notify_on.contains(&NotifyOn::Conflict) && data.commit.warnings.conflict.is_none()
+        {
+            data.commit.warnings.conflict = state
+                .commit(command_helper)?
+                .as_ref()
+                .map(|c| c.has_conflict());
+        }
+        if global.notify_on.contains(&NotifyOn::Divergent)
+            && data.commit.warnings.divergent.is_none()
+        {
+            super::state::State::default().parse_hidden_and_divergent(
+                command_helper,
+                state,
+                data,
+                global,
+            )?;
+        }
+
+        Ok(global
+            .notify_on
+            .iter()
+            .copied()
+            .filter(|kind| match kind {
+                NotifyOn::Conflict => data.commit.warnings.conflict == Some(true),
+                NotifyOn::Divergent => data.commit.warnings.divergent == Some(true),
+            })
+            .collect())
+    })
+}
+
+/// Notifies about the `active` states that weren't active at the last prompt, as recorded in the
+/// state file at `path`. The states are only computed when the operation changed since.
+fn notify_changes(
+    path: &Path,
+    op_id: &str,
+    io: &mut impl Write,
+    active: impl FnOnce() -> Result<Vec<NotifyOn>, CommandError>,
+) -> Result<(), CommandError> {
+    let previous = match std::fs::read_to_string(path) {
+        Ok(recorded) => match parse_states(&recorded, op_id) {
+            Some(previous) => previous,
+            None => return Ok(()),
+        },
+        Err(_) => Vec::new(),
+    };
+    let active = active()?;
+
+    for kind in pending(&active, &previous) {
+        write_notification(io, kind)?;
+    }
+
+    // Best effort: without the record the notification is just sent again.
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = std::fs::write(path, format_states(op_id, &active));
+
+    Ok(())
+}
+
+/// States that are active now but weren't when the last notification was sent.
+fn pending(active: &[NotifyOn], previous: &[NotifyOn]) -> Vec<NotifyOn> {
+    active
+        .iter()
+        .copied()
+        .filter(|kind| !previous.contains(kind))
+        .collect()
+}
+
+fn write_notification(io: &mut impl Write, kind: NotifyOn) -> Result<(), CommandError> {
+    write!(io, "\x1b]9;{}\x07", kind.message())?;
+    Ok(())
+}
+
+/// The states recorded at the last prompt, `None` if they were recorded for `op_id` already: the
+/// repo didn't change since, so there is nothing new to notify about.
+fn parse_states(s: &str, op_id: &str) -> Option<Vec<NotifyOn>> {
+    let (recorded_op_id, states) = s.split_once('\n').unwrap_or((s, ""));
+    if recorded_op_id == op_id {
+        return None;
+    }
+    Some(states.lines().filter_map(NotifyOn::from_name).collect())
+}
+
+/// State files hold the operation id on the first line, followed by the active states.
+fn format_states(op_id: &str, states: &[NotifyOn]) -> String {
+    let states: String = states
+        .iter()
+        .map(|kind| format!("{}\n", kind.name()))
+        .collect();
+    format!("{op_id}\n{states}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders the notifications for a prompt at `op_id` against the state file at `path`.
+    fn render(path: &Path, op_id: &str, active: &[NotifyOn]) -> String {
+        crate::config::rendered(|io| notify_changes(path, op_id, io, || Ok(active.to_vec())))
+    }
+
+    #[test]
+    fn notifies_once_per_state_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify");
+        let mut op = 0;
+        let mut next_op = |active: &[NotifyOn]| {
+            op += 1;
+            render(&path, &format!("op{op}"), active)
+        };
+
+        assert_eq!(
+            next_op(&[NotifyOn::Conflict]),
+            "\x1b]9;jj: working copy has conflicts\x07"
+        );
+        assert_eq!(next_op(&[NotifyOn::Conflict]), "");
+        assert_eq!(
+            next_op(&[NotifyOn::Conflict, NotifyOn::Divergent]),
+            "\x1b]9;jj: working copy is divergent\x07"
+        );
+        assert_eq!(next_op(&[]), "");
+        assert_eq!(
+            next_op(&[NotifyOn::Conflict]),
+            "\x1b]9;jj: working copy has conflicts\x07"
+        );
+    }
+
+    #[test]
+    fn same_operation_is_not_checked_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache").join("notify");
+
+        assert_eq!(
+            render(&path, "op1", &[NotifyOn::Conflict]),
+            "\x1b]9;jj: working copy has conflicts\x07"
+        );
+        // The working copy can't change without a new operation, so it isn't checked again.
+        let out = crate::config::rendered(|io| {
+            notify_changes(&path, "op1", io, || panic!("states were checked again"))
+        });
+        assert_eq!(out, "");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "op1\nConflict\n");
+    }
+}
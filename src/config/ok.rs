@@ -0,0 +1,128 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints a symbol when the working copy is ready to push: it has a description, contains changes
+/// and no warning applies to it.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OkModule {
+    /// Text that will be printed when the working copy is ready.
+    #[serde(default = "default_symbol")]
+    symbol: String,
+    /// Also treat an empty working copy commit as ready, e.g. for intentionally empty merges.
+    #[serde(default)]
+    allow_empty: bool,
+    /// Controls how the symbol is rendered.
+    #[serde(flatten)]
+    style: Style,
+}
+
+fn default_symbol() -> String {
+    "✓".to_string()
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Green),
+        ..Default::default()
+    }
+}
+
+impl Default for OkModule {
+    fn default() -> Self {
+        Self {
+            symbol: default_symbol(),
+            allow_empty: false,
+            style: default_style(),
+        }
+    }
+}
+
+impl OkModule {
+    fn is_ok(&self, data: &crate::JJData) -> bool {
+        let warnings = &data.commit.warnings;
+        let described = data.commit.described == Some(true);
+        let changed = self.allow_empty || warnings.empty == Some(false);
+        let warned = [
+            warnings.conflict,
+            warnings.divergent,
+            warnings.hidden,
+            warnings.immutable,
+        ]
+        .contains(&Some(true));
+        described && changed && !warned
+    }
+
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        if !self.is_ok(data) {
+            return Ok(());
+        }
+        self.style.print(io, default_style(), prev_style)?;
+        write!(io, "{}", self.symbol)?;
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        super::commit::resolve_described(command_helper, state, data)?;
+        super::state::State::default().parse(command_helper, state, data, global)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &OkModule, described: bool) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                described: Some(described),
+                warnings: crate::CommitWarnings {
+                    conflict: Some(false),
+                    divergent: Some(false),
+                    hidden: Some(false),
+                    immutable: Some(false),
+                    empty: Some(false),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn renders_when_all_green() {
+        assert_eq!(render(&OkModule::default(), true), "\u{1b}[0m\u{1b}[32m✓");
+    }
+
+    #[test]
+    fn renders_nothing_without_description() {
+        assert_eq!(render(&OkModule::default(), false), "");
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::Write;
+
+use jj_cli::command_error::CommandError;
+use jj_lib::op_walk;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints a badge describing the last operation, e.g. `new` right after `jj new`.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OperationBadge {
+    /// Rules that are checked in order. The first rule matching the operation is rendered.
+    #[serde(default = "default_rules")]
+    rules: Vec<BadgeRule>,
+    /// Text that will be printed when no rule matches the operation.
+    #[serde(default)]
+    default: Option<String>,
+    /// Controls how the badge is rendered unless a rule overrides it.
+    #[serde(flatten)]
+    style: Style,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+struct BadgeRule {
+    /// The operation description has to start with this text for the rule to match.
+    prefix: String,
+    /// The text that will be printed when this rule matches.
+    badge: String,
+    /// Controls how the badge is rendered when this rule matches.
+    #[serde(flatten, default)]
+    style: Style,
+}
+
+fn rule(prefix: &str, badge: &str) -> BadgeRule {
+    BadgeRule {
+        prefix: prefix.to_string(),
+        badge: badge.to_string(),
+        style: Default::default(),
+    }
+}
+
+fn default_rules() -> Vec<BadgeRule> {
+    vec![
+        rule("new empty commit", "new"),
+        rule("squash", "squash"),
+        rule("rebase", "rebase"),
+        rule("abandon", "abandon"),
+        rule("describe", "describe"),
+        rule("undo", "undo"),
+    ]
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Cyan),
+        ..Default::default()
+    }
+}
+
+impl Default for OperationBadge {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            default: None,
+            style: default_style(),
+        }
+    }
+}
+
+impl OperationBadge {
+    fn badge(&self, description: &str) -> Option<(&str, &Style)> {
+        match self
+            .rules
+            .iter()
+            .find(|rule| description.starts_with(&rule.prefix))
+        {
+            Some(rule) => Some((&rule.badge, &rule.style)),
+            None => self.default.as_deref().map(|badge| (badge, &self.style)),
+        }
+    }
+
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(Some(description)) = &data.operation else {
+            return Ok(());
+        };
+        let Some((badge, style)) = self.badge(description) else {
+            return Ok(());
+        };
+
+        style.print(
+            io,
+            self.style.merge_with_fallback(Some(default_style())),
+            prev_style,
+        )?;
+        write!(io, "{badge}")?;
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        _global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.operation.is_some() {
+            return Ok(());
+        }
+        let repo = state.repo(command_helper)?;
+
+        // Snapshots are taken implicitly by almost every command, so they don't count as the last operation.
+        let mut operation = None;
+        for op in op_walk::walk_ancestors(std::slice::from_ref(repo.operation())) {
+            let op = op?;
+            if !op.metadata().is_snapshot {
+                operation = Some(op.metadata().description.clone());
+                break;
+            }
+        }
+        data.operation = Some(operation);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &OperationBadge, description: &str) -> String {
+        let data = crate::JJData {
+            operation: Some(Some(description.to_string())),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn maps_operation_to_badge() {
+        let config = OperationBadge::default();
+        assert_eq!(
+            render(&config, "new empty commit"),
+            "\u{1b}[0m\u{1b}[36mnew"
+        );
+        assert_eq!(
+            render(
+                &config,
+                "squash commits into 0a1b2c3d4e5f60718293a4b5c6d7e8f901234567"
+            ),
+            "\u{1b}[0m\u{1b}[36msquash"
+        );
+    }
+
+    #[test]
+    fn unknown_operation_renders_default() {
+        let mut config = OperationBadge::default();
+        assert_eq!(render(&config, "create bookmark main"), "");
+
+        config.default = Some("op".to_string());
+        assert_eq!(
+            render(&config, "create bookmark main"),
+            "\u{1b}[0m\u{1b}[36mop"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::io::Write;
+
+use jj_cli::{cli_util::RevisionArg, command_error::CommandError, ui::Ui};
+use jj_lib::object_id::ObjectId as _;
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints the bookmarks between `trunk()` and the working copy as a breadcrumb, e.g. `main → feature → @+2`.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Path {
+    /// Text that will be rendered between each entry.
+    #[serde(default = "default_separator")]
+    separator: String,
+    /// Maximum amount of bookmarks that will be rendered, the ones closest to the working copy are kept.
+    #[serde(default = "default_max_entries")]
+    max_entries: usize,
+    /// Text that replaces the bookmarks that were cut off by `max_entries`.
+    #[serde(default = "default_truncation_symbol")]
+    truncation_symbol: String,
+    /// Controls how the bookmarks are rendered.
+    #[serde(flatten)]
+    style: Style,
+    /// Controls how the working copy entry is rendered.
+    #[serde(default = "default_current_style")]
+    current: Style,
+}
+
+fn default_separator() -> String {
+    " → ".to_string()
+}
+
+fn default_max_entries() -> usize {
+    3
+}
+
+fn default_truncation_symbol() -> String {
+    "…".to_string()
+}
+
+fn default_style() -> Style {
+    Style {
+        color: Some(Color::Magenta),
+        ..Default::default()
+    }
+}
+
+fn default_current_style() -> Style {
+    Style {
+        color: Some(Color::BrightBlack),
+        ..Default::default()
+    }
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self {
+            separator: default_separator(),
+            max_entries: default_max_entries(),
+            truncation_symbol: default_truncation_symbol(),
+            style: default_style(),
+            current: default_current_style(),
+        }
+    }
+}
+
+impl Path {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(entries) = &data.path else {
+            return Ok(());
+        };
+
+        if !entries.is_empty() {
+            let skipped = entries.len().saturating_sub(self.max_entries);
+            self.style.print(io, default_style(), prev_style)?;
+            if skipped > 0 {
+                write!(io, "{}{}", self.truncation_symbol, self.separator)?;
+            }
+            for (i, entry) in entries.iter().skip(skipped).enumerate() {
+                if i != 0 {
+                    write!(io, "{}", self.separator)?;
+                }
+                write!(io, "{}", global.escape.apply(&entry.name))?;
+            }
+        }
+
+        let distance = entries.last().map(|entry| entry.distance);
+        if distance == Some(0) {
+            return Ok(());
+        }
+        if distance.is_some() {
+            write!(io, "{}", self.separator)?;
+        }
+        self.current
+            .print(io, default_current_style(), prev_style)?;
+        match distance {
+            Some(distance) => write!(io, "@+{distance}")?,
+            None => write!(io, "@")?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.path.is_some() {
+            return Ok(());
+        }
+
+        let workspace_helper = state.workspace_helper(command_helper)?;
+        let view = workspace_helper.repo().view();
+        let commit_ids = workspace_helper
+            .parse_revset(&Ui::null(), &RevisionArg::from("trunk()::@".to_string()))?
+            .evaluate_to_commit_ids()?
+            .take(global.bookmarks.search_depth + 1)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut candidates = Vec::new();
+        for commit_id in &commit_ids {
+            if let Some(bookmark) = crate::select_bookmark(crate::collect_bookmarks_for_commit(
+                commit_id,
+                view,
+                &global.bookmarks,
+                0,
+            )) {
+                candidates.push(bookmark);
+            }
+        }
+
+        let mut entries = Vec::new();
+        for mut bookmark in candidates {
+            // `x::@` contains x itself.
+            bookmark.distance = state
+                .count_revset(command_helper, &format!("{}::@", bookmark.target.hex()))?
+                .saturating_sub(1);
+            entries.push(bookmark);
+        }
+
+        data.path = Some(breadcrumb(entries));
+        Ok(())
+    }
+}
+
+/// Orders the bookmarks along the path from trunk to the working copy.
+fn breadcrumb(mut entries: Vec<crate::Bookmark>) -> Vec<crate::Bookmark> {
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.distance));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::bookmark;
+
+    fn render(config: &Path, entries: Vec<crate::Bookmark>) -> String {
+        let data = crate::JJData {
+            path: Some(breadcrumb(entries)),
+            ..Default::default()
+        };
+        crate::config::rendered(|io| {
+            config.print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        })
+    }
+
+    #[test]
+    fn renders_stack_from_trunk() {
+        let out = render(
+            &Path::default(),
+            vec![bookmark("feature", 2), bookmark("main", 5)],
+        );
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35mmain → feature → \u{1b}[90m@+2");
+    }
+
+    #[test]
+    fn caps_entries() {
+        let config = Path {
+            max_entries: 1,
+            ..Default::default()
+        };
+        let out = render(&config, vec![bookmark("feature", 0), bookmark("main", 5)]);
+        assert_eq!(out, "\u{1b}[0m\u{1b}[35m… → feature");
+    }
+
+    #[test]
+    fn renders_working_copy_without_bookmarks() {
+        assert_eq!(render(&Path::default(), Vec::new()), "\u{1b}[0m\u{1b}[90m@");
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::Write;
+
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::Color;
+
+/// Renders every module that printed something as a powerline segment with its own background.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Powerline {
+    /// Glyph that will be printed before the first segment.
+    #[serde(default = "default_left_glyph")]
+    left_glyph: String,
+    /// Glyph that will be printed after each segment, transitioning into the next segment's background.
+    #[serde(default = "default_right_glyph")]
+    right_glyph: String,
+    /// Backgrounds of the segments, repeated if there are more segments than colors.
+    #[serde(default = "default_palette")]
+    palette: Vec<Color>,
+}
+
+fn default_left_glyph() -> String {
+    "\u{e0b6}".to_string()
+}
+
+fn default_right_glyph() -> String {
+    "\u{e0b0}".to_string()
+}
+
+fn default_palette() -> Vec<Color> {
+    vec![Color::Black, Color::BrightBlack]
+}
+
+impl Default for Powerline {
+    fn default() -> Self {
+        Self {
+            left_glyph: default_left_glyph(),
+            right_glyph: default_right_glyph(),
+            palette: default_palette(),
+        }
+    }
+}
+
+/// Writes modules as consecutive powerline segments.
+pub(super) struct Segments<'a> {
+    config: &'a Powerline,
+    /// Number of segments written so far.
+    count: usize,
+}
+
+impl<'a> Segments<'a> {
+    pub(super) fn new(config: &'a Powerline) -> Self {
+        Self { config, count: 0 }
+    }
+
+    fn background(&self, segment: usize) -> Option<Color> {
+        if self.config.palette.is_empty() {
+            return None;
+        }
+        Some(self.config.palette[segment % self.config.palette.len()])
+    }
+
+    pub(super) fn write(&mut self, io: &mut impl Write, module: &[u8]) -> std::io::Result<()> {
+        let background = self.background(self.count);
+        match self.count.checked_sub(1) {
+            None => glyph(io, &self.config.left_glyph, background, None)?,
+            Some(previous) => glyph(
+                io,
+                &self.config.right_glyph,
+                self.background(previous),
+                background,
+            )?,
+        }
+
+        let module = String::from_utf8_lossy(module);
+        match background {
+            Some(background) => {
+                let reset = nu_ansi_term::Style {
+                    background: Some(background.into()),
+                    prefix_with_reset: true,
+                    ..Default::default()
+                };
+                let code = nu_ansi_term::Style::new().on(background.into()).prefix();
+                write!(
+                    io,
+                    "{}{}",
+                    reset.prefix(),
+                    super::util::with_background(&module, &code.to_string())
+                )?;
+            }
+            None => write!(io, "{module}")?,
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    pub(super) fn finish(&mut self, io: &mut impl Write) -> std::io::Result<()> {
+        if let Some(last) = self.count.checked_sub(1) {
+            glyph(io, &self.config.right_glyph, self.background(last), None)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the segments of the current line, so the next line starts with the `left_glyph`.
+    pub(super) fn line_break(&mut self, io: &mut impl Write) -> std::io::Result<()> {
+        self.finish(io)?;
+        self.count = 0;
+        Ok(())
+    }
+}
+
+/// Prints `glyph` in `foreground` on `background`, so it blends into the adjacent segments.
+fn glyph(
+    io: &mut impl Write,
+    glyph: &str,
+    foreground: Option<Color>,
+    background: Option<Color>,
+) -> std::io::Result<()> {
+    let style = nu_ansi_term::Style {
+        foreground: foreground.map(Into::into),
+        background: background.map(Into::into),
+        prefix_with_reset: true,
+        ..Default::default()
+    };
+    write!(io, "{}{glyph}", style.prefix())
+}
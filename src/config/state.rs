@@ -1,7 +1,7 @@
-use std::io::Write;
+use std::{borrow::Cow, collections::BTreeSet, io::Write};
 
 use jj_cli::{cli_util::RevisionArg, command_error::CommandError, ui::Ui};
-use jj_lib::repo::Repo;
+use jj_lib::{merged_tree::MergedTree, object_id::ObjectId as _, repo::Repo};
 #[cfg(feature = "json-schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -12,21 +12,67 @@ use super::util::Style;
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[derive(Deserialize, Serialize, Debug)]
 pub struct State {
-    /// Text that will be printed between each warning.
+    /// Text that will be printed between consecutive active warnings when `combine` isn't set.
     #[serde(default = "default_separator")]
     separator: String,
+    /// Render all active warnings as a single group like `(conflict,empty)`.
+    #[serde(default)]
+    combine: bool,
+    /// Text that will be printed before the group when `combine` is set.
+    #[serde(default = "default_group_prefix")]
+    group_prefix: String,
+    /// Text that will be printed after the group when `combine` is set.
+    #[serde(default = "default_group_suffix")]
+    group_suffix: String,
+    /// Text that will be printed between warnings inside the group when `combine` is set.
+    #[serde(default = "default_inner_separator")]
+    inner_separator: String,
+    /// Controls how the group is rendered when `combine` is set.
+    #[serde(default)]
+    group_style: Style,
+    /// Default style of the warnings and the separators between them. Each warning's own style
+    /// takes precedence for the keys it sets.
+    #[serde(default)]
+    style: Style,
     /// Controls how the conflict warning will be rendered.
     #[serde(default = "default_conflict")]
     conflict: Status,
+    /// Render `inherited_conflict` instead of `conflict` when every parent is already conflicted at
+    /// the same paths.
+    #[serde(default)]
+    conflict_detail: bool,
+    /// Controls how the conflict warning will be rendered when `conflict_detail` is set and the
+    /// conflict was inherited from a parent.
+    #[serde(default = "default_inherited_conflict")]
+    inherited_conflict: Status,
     /// Controls how the divergence warning will be rendered.
     #[serde(default = "default_divergent")]
     divergent: Status,
+    /// Count the visible commits sharing the working copy's change id, rendering
+    /// `divergent_count_text` instead of the divergent warning's text.
+    #[serde(default)]
+    show_divergent_count: bool,
+    /// Text that will be rendered for the divergent warning when `show_divergent_count` is set.
+    /// `{count}` is replaced with the number of commits.
+    #[serde(default = "default_divergent_count_text")]
+    divergent_count_text: String,
     /// Controls how the empty warning will be rendered.
     #[serde(default = "default_empty")]
     empty: Status,
     /// Controls how the immutable warning will be rendered.
     #[serde(default = "default_immutable")]
     immutable: Status,
+    /// Name the bookmarks on the immutable heads the working copy descends from, rendering
+    /// `immutable_heads_text` instead of the immutable warning's text.
+    #[serde(default)]
+    show_immutable_heads: bool,
+    /// Text that will be rendered for the immutable warning when `show_immutable_heads` is set.
+    /// `{heads}` is replaced with the bookmark names.
+    #[serde(default = "default_immutable_heads_text")]
+    immutable_heads_text: String,
+    /// Maximum amount of immutable heads that will be named.
+    #[serde(default = "default_max_immutable_heads")]
+    max_immutable_heads: usize,
     /// Controls how the hidden warning will be rendered.
     #[serde(default = "default_hidden")]
     hidden: Status,
@@ -36,6 +82,18 @@ fn default_separator() -> String {
     " ".to_string()
 }
 
+fn default_group_prefix() -> String {
+    "(".to_string()
+}
+
+fn default_group_suffix() -> String {
+    ")".to_string()
+}
+
+fn default_inner_separator() -> String {
+    ",".to_string()
+}
+
 fn default_conflict() -> Status {
     Status {
         text: "(CONFLICT)".to_string(),
@@ -47,6 +105,29 @@ fn default_conflict() -> Status {
     }
 }
 
+fn default_inherited_conflict() -> Status {
+    Status {
+        text: "(CONFLICT↑)".to_string(),
+        style: Style {
+            color: Some(super::util::Color::Red),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn default_divergent_count_text() -> String {
+    "(DIVERGENT {count})".to_string()
+}
+
+fn default_immutable_heads_text() -> String {
+    "(IMMUTABLE: {heads})".to_string()
+}
+
+fn default_max_immutable_heads() -> usize {
+    2
+}
+
 fn default_immutable() -> Status {
     Status {
         text: "(IMMUTABLE)".to_string(),
@@ -107,11 +188,24 @@ impl Default for State {
     fn default() -> Self {
         Self {
             separator: default_separator(),
+            combine: false,
+            group_prefix: default_group_prefix(),
+            group_suffix: default_group_suffix(),
+            inner_separator: default_inner_separator(),
+            group_style: Default::default(),
+            style: Default::default(),
             conflict: default_conflict(),
+            conflict_detail: false,
+            inherited_conflict: default_inherited_conflict(),
             divergent: default_divergent(),
+            show_divergent_count: false,
+            divergent_count_text: default_divergent_count_text(),
             hidden: default_hidden(),
             empty: default_empty(),
             immutable: default_immutable(),
+            show_immutable_heads: false,
+            immutable_heads_text: default_immutable_heads_text(),
+            max_immutable_heads: default_max_immutable_heads(),
         }
     }
 }
@@ -121,51 +215,99 @@ impl State {
         &self,
         io: &mut impl Write,
         data: &crate::JJData,
-        module_separator: &str,
+        global: &super::GlobalConfig,
         prev_style: &mut Option<nu_ansi_term::Style>,
     ) -> Result<(), CommandError> {
-        let mut first = true;
-        if let Some(true) = data.commit.warnings.conflict {
-            self.conflict.style.print(io, None, prev_style)?;
-            first = false;
-            write!(io, "{}", self.conflict.text)?;
-        }
-        if let Some(true) = data.commit.warnings.divergent {
-            if !first {
-                write!(io, "{}", self.separator)?;
+        let warnings = &data.commit.warnings;
+        let empty = shown_empty(data, global);
+        let conflict = match warnings.conflict_source {
+            Some(crate::ConflictSource::Inherited) if self.conflict_detail => {
+                &self.inherited_conflict
             }
-            first = false;
-            self.divergent.style.print(io, None, prev_style)?;
-            write!(io, "{}", self.divergent.text)?;
+            _ => &self.conflict,
+        };
+        let active: Vec<(&Status, Cow<str>)> = [
+            (
+                conflict,
+                warnings.conflict,
+                Cow::from(conflict.text.as_str()),
+            ),
+            (
+                &self.divergent,
+                warnings.divergent,
+                self.divergent_text(warnings),
+            ),
+            (
+                &self.hidden,
+                warnings.hidden,
+                Cow::from(self.hidden.text.as_str()),
+            ),
+            (
+                &self.immutable,
+                warnings.immutable,
+                self.immutable_text(warnings),
+            ),
+            (&self.empty, empty, Cow::from(self.empty.text.as_str())),
+        ]
+        .into_iter()
+        .filter(|(status, active, _)| !status.disabled && *active == Some(true))
+        .map(|(status, _, text)| (status, text))
+        .collect();
+
+        if active.is_empty() {
+            return Ok(());
         }
-        if let Some(true) = data.commit.warnings.hidden {
-            if !first {
-                write!(io, "{}", self.separator)?;
+
+        if self.combine {
+            self.group_style.print(io, None, prev_style)?;
+            write!(io, "{}", self.group_prefix)?;
+            for (i, (_, text)) in active.iter().enumerate() {
+                if i != 0 {
+                    write!(io, "{}", self.inner_separator)?;
+                }
+                write!(io, "{text}")?;
             }
-            first = false;
-            self.hidden.style.print(io, None, prev_style)?;
-            write!(io, "{}", self.hidden.text)?;
+            write!(io, "{}", self.group_suffix)?;
+            return Ok(());
         }
-        if let Some(true) = data.commit.warnings.immutable {
-            if !first {
+
+        for (i, (status, text)) in active.iter().enumerate() {
+            if i != 0 {
+                // Don't let the previous warning's background bleed into the separator.
+                self.style.print(io, None, prev_style)?;
                 write!(io, "{}", self.separator)?;
             }
-            first = false;
-            self.immutable.style.print(io, None, prev_style)?;
-            write!(io, "{}", self.immutable.text)?;
+            status.style.print(io, self.style.clone(), prev_style)?;
+            write!(io, "{text}")?;
         }
-        if let Some(true) = data.commit.warnings.empty {
-            if !first {
-                write!(io, "{}", self.separator)?;
-            }
-            first = false;
-            self.empty.style.print(io, None, prev_style)?;
-            write!(io, "{}", self.empty.text)?;
+        Ok(())
+    }
+
+    /// The divergent warning's text, with the number of divergent commits if requested.
+    fn divergent_text(&self, warnings: &crate::CommitWarnings) -> Cow<'_, str> {
+        match warnings.divergent_count {
+            Some(count) if self.show_divergent_count => Cow::Owned(
+                self.divergent_count_text
+                    .replace("{count}", &count.to_string()),
+            ),
+            _ => Cow::Borrowed(&self.divergent.text),
         }
-        if !first {
-            write!(io, "{module_separator}")?;
+    }
+
+    /// The immutable warning's text, naming the immutable heads if requested and any were found.
+    fn immutable_text(&self, warnings: &crate::CommitWarnings) -> Cow<'_, str> {
+        match &warnings.immutable_heads {
+            Some(heads) if self.show_immutable_heads && !heads.is_empty() => {
+                let heads = heads
+                    .iter()
+                    .take(self.max_immutable_heads)
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                Cow::Owned(self.immutable_heads_text.replace("{heads}", &heads))
+            }
+            _ => Cow::Borrowed(&self.immutable.text),
         }
-        Ok(())
     }
     pub fn parse(
         &self,
@@ -175,14 +317,47 @@ impl State {
         global: &super::GlobalConfig,
     ) -> Result<(), CommandError> {
         if !self.empty.disabled && data.commit.warnings.empty.is_none() {
+            super::commit::resolve_root(command_helper, state, data)?;
             data.commit.warnings.empty = state.commit_is_empty(command_helper)?;
         }
+        if global.hide_empty_on_fresh_commit
+            && data.commit.warnings.empty == Some(true)
+            && data.commit.warnings.fresh.is_none()
+        {
+            super::commit::resolve_described(command_helper, state, data)?;
+            data.commit.warnings.fresh = Some(
+                data.commit.described == Some(false)
+                    && has_single_visible_parent(command_helper, state)?,
+            );
+        }
         if !self.conflict.disabled && data.commit.warnings.conflict.is_none() {
             data.commit.warnings.conflict = state
                 .commit(command_helper)?
                 .as_ref()
                 .map(|c| c.has_conflict());
         }
+        if self.conflict_detail
+            && data.commit.warnings.conflict == Some(true)
+            && data.commit.warnings.conflict_source.is_none()
+        {
+            let conflicted_paths = |tree: &MergedTree| {
+                tree.conflicts()
+                    .map(|(path, _)| path)
+                    .collect::<BTreeSet<_>>()
+            };
+            let conflicts = state
+                .tree(command_helper)?
+                .as_ref()
+                .map(conflicted_paths)
+                .unwrap_or_default();
+            let parent_conflicts = state
+                .parent_commits(command_helper)?
+                .iter()
+                .map(|parent| Ok(conflicted_paths(&parent.tree()?)))
+                .collect::<Result<Vec<_>, CommandError>>()?;
+            data.commit.warnings.conflict_source =
+                crate::ConflictSource::detect(&conflicts, &parent_conflicts);
+        }
 
         self.parse_hidden_and_divergent(command_helper, state, data, global)?;
 
@@ -200,8 +375,74 @@ impl State {
                 Some(immutable.any(|id| id.as_ref().is_ok_and(|id| id == &commit_id)));
         }
 
+        if self.show_immutable_heads
+            && data.commit.warnings.immutable == Some(true)
+            && data.commit.warnings.immutable_heads.is_none()
+        {
+            let workspace_helper = state.workspace_helper(command_helper)?;
+            let view = workspace_helper.repo().view();
+            let mut names = Vec::new();
+            for id in workspace_helper
+                .parse_revset(
+                    &Ui::null(),
+                    &RevisionArg::from("heads(immutable_heads() & ::@)".to_string()),
+                )?
+                .evaluate_to_commit_ids()?
+            {
+                let mut bookmarks: Vec<String> = view
+                    .local_bookmarks_for_commit(&id?)
+                    .map(|(name, _)| name.as_str().to_string())
+                    .collect();
+                bookmarks.sort();
+                names.extend(bookmarks);
+            }
+            data.commit.warnings.immutable_heads = Some(names);
+        }
+
         Ok(())
     }
+    /// Detects only the warnings for the given `states`, without any details like the conflict's
+    /// source.
+    pub(super) fn detecting(states: &[super::focus::FocusOn]) -> Self {
+        use super::focus::FocusOn;
+
+        let mut detector = Self::default();
+        for (status, on) in [
+            (&mut detector.conflict, FocusOn::Conflict),
+            (&mut detector.divergent, FocusOn::Divergent),
+            (&mut detector.hidden, FocusOn::Hidden),
+            (&mut detector.immutable, FocusOn::Immutable),
+            (&mut detector.empty, FocusOn::Empty),
+        ] {
+            status.disabled = !states.contains(&on);
+        }
+        detector
+    }
+
+    /// Drops the warnings this module renders, keeping the ones it has switched off for the
+    /// modules that resolved them.
+    pub(crate) fn invalidate(&self, data: &mut crate::JJData) {
+        let warnings = &mut data.commit.warnings;
+        if !self.empty.disabled {
+            warnings.empty = None;
+            warnings.fresh = None;
+        }
+        if !self.conflict.disabled {
+            warnings.conflict = None;
+            warnings.conflict_source = None;
+        }
+        if !self.hidden.disabled {
+            warnings.hidden = None;
+        }
+        if !self.divergent.disabled {
+            warnings.divergent = None;
+            warnings.divergent_count = None;
+        }
+        if !self.immutable.disabled {
+            warnings.immutable = None;
+            warnings.immutable_heads = None;
+        }
+    }
     pub fn parse_hidden_and_divergent(
         &self,
         command_helper: &jj_cli::cli_util::CommandHelper,
@@ -223,7 +464,10 @@ impl State {
                 Some(commits) => match commits.len() {
                     0 => data.commit.warnings.hidden = Some(true),
                     1 => {}
-                    _ => data.commit.warnings.divergent = Some(true),
+                    count => {
+                        data.commit.warnings.divergent = Some(true);
+                        data.commit.warnings.divergent_count = Some(count);
+                    }
                 },
                 None => data.commit.warnings.hidden = Some(true),
             }
@@ -231,3 +475,256 @@ impl State {
         Ok(())
     }
 }
+
+/// Whether the working copy commit counts as empty: the root commit never does, and neither does a
+/// fresh one if `hide_empty_on_fresh_commit` is set.
+pub(super) fn shown_empty(data: &crate::JJData, global: &super::GlobalConfig) -> Option<bool> {
+    match data.commit.warnings.fresh {
+        _ if data.commit.root == Some(true) => Some(false),
+        Some(true) if global.hide_empty_on_fresh_commit => Some(false),
+        _ => data.commit.warnings.empty,
+    }
+}
+
+/// Whether the working copy commit has a single parent that is still visible.
+fn has_single_visible_parent(
+    command_helper: &jj_cli::cli_util::CommandHelper,
+    state: &mut crate::State,
+) -> Result<bool, CommandError> {
+    let parents = state.parent_commits(command_helper)?;
+    let [parent] = parents.as_slice() else {
+        return Ok(false);
+    };
+    let parent = parent.id().hex();
+    Ok(state.count_revset(command_helper, &format!("{parent} & ::visible_heads()"))? == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &State, warnings: crate::CommitWarnings) -> String {
+        render_with(config, warnings, &Default::default())
+    }
+
+    fn render_with(
+        config: &State,
+        warnings: crate::CommitWarnings,
+        global: &crate::config::GlobalConfig,
+    ) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                warnings,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| config.print(io, &data, global, &mut None))
+    }
+
+    #[test]
+    fn combines_active_warnings() {
+        let mut config = State {
+            combine: true,
+            ..Default::default()
+        };
+        config.conflict.text = "conflict".to_string();
+        config.empty.text = "empty".to_string();
+
+        let out = render(
+            &config,
+            crate::CommitWarnings {
+                conflict: Some(true),
+                empty: Some(true),
+                divergent: Some(false),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[m(conflict,empty)");
+    }
+
+    #[test]
+    fn separator_between_warnings() {
+        let mut config = State {
+            separator: " | ".to_string(),
+            ..Default::default()
+        };
+        for status in [
+            &mut config.conflict,
+            &mut config.divergent,
+            &mut config.empty,
+        ] {
+            status.style = Style::default();
+        }
+        config.conflict.text = "conflict".to_string();
+        config.divergent.text = "divergent".to_string();
+        config.empty.text = "empty".to_string();
+
+        let out = render(
+            &config,
+            crate::CommitWarnings {
+                conflict: Some(true),
+                divergent: Some(true),
+                empty: Some(true),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[mconflict | divergent | empty");
+    }
+
+    #[test]
+    fn warnings_fall_back_to_module_style() {
+        let mut config = State {
+            style: Style {
+                color: Some(super::super::util::Color::White),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.conflict.style.bg_color = Some(super::super::util::Color::Yellow);
+        config.empty.style = Style::default();
+
+        let out = render(
+            &config,
+            crate::CommitWarnings {
+                conflict: Some(true),
+                empty: Some(true),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            out,
+            "\u{1b}[0m\u{1b}[43;31m(CONFLICT)\u{1b}[0m\u{1b}[0m\u{1b}[37m (EMPTY)"
+        );
+    }
+
+    #[test]
+    fn detecting_only_enables_the_given_states() {
+        let detector = State::detecting(&[super::super::focus::FocusOn::Divergent]);
+
+        assert!(!detector.divergent.disabled);
+        assert!(detector.conflict.disabled);
+        assert!(detector.hidden.disabled);
+        assert!(detector.immutable.disabled);
+        assert!(detector.empty.disabled);
+    }
+
+    #[test]
+    fn divergent_count() {
+        let mut config = State::default();
+        config.divergent.style = Style::default();
+        let warnings = || crate::CommitWarnings {
+            divergent: Some(true),
+            divergent_count: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(render(&config, warnings()), "\u{1b}[0m\u{1b}[m(DIVERGENT)");
+        config.show_divergent_count = true;
+        assert_eq!(
+            render(&config, warnings()),
+            "\u{1b}[0m\u{1b}[m(DIVERGENT 3)"
+        );
+        config.divergent_count_text = "⑂{count}".to_string();
+        assert_eq!(render(&config, warnings()), "\u{1b}[0m\u{1b}[m⑂3");
+    }
+
+    #[test]
+    fn combine_renders_nothing_without_warnings() {
+        let config = State {
+            combine: true,
+            ..Default::default()
+        };
+
+        assert_eq!(render(&config, Default::default()), "");
+    }
+
+    #[test]
+    fn hides_empty_on_fresh_commit() {
+        let mut config = State::default();
+        config.empty.text = "empty".to_string();
+        let warnings = || crate::CommitWarnings {
+            empty: Some(true),
+            fresh: Some(true),
+            ..Default::default()
+        };
+        let global = crate::config::GlobalConfig {
+            hide_empty_on_fresh_commit: true,
+            ..Default::default()
+        };
+
+        assert_eq!(render_with(&config, warnings(), &global), "");
+        assert!(render(&config, warnings()).ends_with("empty"));
+    }
+
+    #[test]
+    fn disabled_warning_is_not_rendered() {
+        let mut config = State::default();
+        config.empty.disabled = true;
+        let warnings = || crate::CommitWarnings {
+            empty: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(render(&config, warnings()), "");
+
+        config.empty.disabled = false;
+        assert!(render(&config, warnings()).ends_with("(EMPTY)"));
+    }
+
+    #[test]
+    fn conflict_detail_distinguishes_inherited_conflicts() {
+        let config = State {
+            conflict_detail: true,
+            ..Default::default()
+        };
+        let paths = |paths: &[&'static str]| paths.iter().copied().collect::<BTreeSet<_>>();
+        let warnings = |conflicts: &[&'static str], parents: &[&[&'static str]]| {
+            let parents: Vec<_> = parents.iter().map(|parent| paths(parent)).collect();
+            crate::CommitWarnings {
+                conflict: Some(true),
+                conflict_source: crate::ConflictSource::detect(&paths(conflicts), &parents),
+                ..Default::default()
+            }
+        };
+
+        assert!(render(&config, warnings(&["a"], &[&["a"]])).ends_with("(CONFLICT↑)"));
+        assert!(render(&config, warnings(&["a"], &[&["a", "b"], &["a"]])).ends_with("(CONFLICT↑)"));
+        // Only one side of the merge has the conflict, the merge itself brings it in.
+        assert!(render(&config, warnings(&["a"], &[&[], &["a"]])).ends_with("(CONFLICT)"));
+        // The parent's conflict is resolved, but a new one appeared at another path.
+        assert!(render(&config, warnings(&["a", "b"], &[&["a"]])).ends_with("(CONFLICT)"));
+        assert!(render(&config, warnings(&["a"], &[])).ends_with("(CONFLICT)"));
+    }
+
+    #[test]
+    fn names_immutable_heads() {
+        let mut config = State {
+            show_immutable_heads: true,
+            ..Default::default()
+        };
+        config.immutable.style = Style::default();
+        let warnings = |heads: &[&str]| crate::CommitWarnings {
+            immutable: Some(true),
+            immutable_heads: Some(heads.iter().map(|head| head.to_string()).collect()),
+            ..Default::default()
+        };
+
+        // `@` descends from the immutable `main`.
+        assert_eq!(
+            render(&config, warnings(&["main"])),
+            "\u{1b}[0m\u{1b}[m(IMMUTABLE: main)"
+        );
+        assert_eq!(
+            render(&config, warnings(&["main", "release", "trunk"])),
+            "\u{1b}[0m\u{1b}[m(IMMUTABLE: main,release)"
+        );
+        assert_eq!(
+            render(&config, warnings(&[])),
+            "\u{1b}[0m\u{1b}[m(IMMUTABLE)"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::{collections::BTreeMap, io::Write};
 
 use super::util::{Color, Style};
 use jj_cli::command_error::CommandError;
@@ -16,37 +16,112 @@ pub struct Symbol {
     /// Controls how the symbol is rendered.
     #[serde(flatten)]
     style: Style,
+    /// Replaces the symbol and style while the working copy is in the given state, e.g.
+    /// `variants.conflict = { symbol = "!", color = "Red" }`. Conflict wins over divergent, which
+    /// wins over empty.
+    #[serde(default)]
+    variants: BTreeMap<SymbolState, Variant>,
 }
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolState {
+    /// conflict => The working copy contains conflicts
+    Conflict,
+    /// divergent => The working copy's change is divergent
+    Divergent,
+    /// empty => The working copy commit is empty
+    Empty,
+    /// default => None of the other states apply
+    Default,
+}
+
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Variant {
+    /// Text that will be rendered instead of the symbol.
+    #[serde(default)]
+    symbol: Option<String>,
+    /// Controls how the symbol is rendered, falling back to the module's style.
+    #[serde(flatten)]
+    style: Style,
+}
+
 impl Symbol {
+    /// The variant for the working copy's current state, if one is configured.
+    fn variant(&self, data: &crate::JJData, global: &super::GlobalConfig) -> Option<&Variant> {
+        let warnings = &data.commit.warnings;
+        let state = if warnings.conflict == Some(true) {
+            SymbolState::Conflict
+        } else if warnings.divergent == Some(true) {
+            SymbolState::Divergent
+        } else if super::state::shown_empty(data, global) == Some(true) {
+            SymbolState::Empty
+        } else {
+            SymbolState::Default
+        };
+        self.variants
+            .get(&state)
+            .or_else(|| self.variants.get(&SymbolState::Default))
+    }
+
     pub fn print(
         &self,
         io: &mut impl Write,
-        _data: &crate::JJData,
-        module_separator: &str,
+        data: &crate::JJData,
+        global: &super::GlobalConfig,
         prev_style: &mut Option<nu_ansi_term::Style>,
     ) -> Result<(), CommandError> {
-        self.style.print(io, default_style(), prev_style)?;
+        let Some(variant) = self.variant(data, global) else {
+            self.style.print(io, default_style(), prev_style)?;
+            write!(io, "{}", self.symbol)?;
+            return Ok(());
+        };
 
-        write!(io, "{}{module_separator}", self.symbol)?;
+        variant.style.print(
+            io,
+            self.style.merge_with_fallback(Some(default_style())),
+            prev_style,
+        )?;
+        write!(io, "{}", variant.symbol.as_ref().unwrap_or(&self.symbol))?;
         Ok(())
     }
 
     pub(crate) fn parse(
         &self,
-        _command_helper: &jj_cli::cli_util::CommandHelper,
-        _state: &mut crate::State,
-        _data: &mut crate::JJData,
-        _global: &super::GlobalConfig,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &super::GlobalConfig,
     ) -> Result<(), CommandError> {
-        Ok(())
+        if self.variants.is_empty() {
+            return Ok(());
+        }
+        detector().parse(command_helper, state, data, global)
+    }
+
+    /// Drops the warnings the variants are picked by.
+    pub(crate) fn invalidate(&self, data: &mut crate::JJData) {
+        if !self.variants.is_empty() {
+            detector().invalidate(data);
+        }
     }
 }
 
+/// Detects only the warnings the variants are picked by.
+fn detector() -> super::state::State {
+    use super::focus::FocusOn;
+
+    super::state::State::detecting(&[FocusOn::Conflict, FocusOn::Divergent, FocusOn::Empty])
+}
+
 impl Default for Symbol {
     fn default() -> Self {
         Self {
             symbol: default_symbol(),
             style: default_style(),
+            variants: BTreeMap::new(),
         }
     }
 }
@@ -60,3 +135,78 @@ fn default_style() -> Style {
 fn default_symbol() -> String {
     "󱗆".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &Symbol, warnings: crate::CommitWarnings) -> String {
+        render_with(config, warnings, &Default::default())
+    }
+
+    fn render_with(
+        config: &Symbol,
+        warnings: crate::CommitWarnings,
+        global: &crate::config::GlobalConfig,
+    ) -> String {
+        let data = crate::JJData {
+            commit: crate::CommitData {
+                warnings,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        crate::config::rendered(|io| config.print(io, &data, global, &mut None))
+    }
+
+    #[test]
+    fn variant_follows_working_copy_state() {
+        let config: Symbol = toml::from_str(
+            r#"
+symbol = "@"
+variants.conflict = { symbol = "!", color = "Red" }
+variants.empty = { symbol = "o" }
+"#,
+        )
+        .unwrap();
+        let warnings = |conflict, empty| crate::CommitWarnings {
+            conflict: Some(conflict),
+            empty: Some(empty),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&config, warnings(false, false)),
+            "\u{1b}[0m\u{1b}[34m@"
+        );
+        assert_eq!(
+            render(&config, warnings(true, true)),
+            "\u{1b}[0m\u{1b}[31m!"
+        );
+        assert_eq!(
+            render(&config, warnings(false, true)),
+            "\u{1b}[0m\u{1b}[34mo"
+        );
+    }
+
+    #[test]
+    fn fresh_commit_is_not_empty_when_hidden() {
+        let config: Symbol =
+            toml::from_str("symbol = \"@\"\nvariants.empty = { symbol = \"o\" }\n").unwrap();
+        let warnings = || crate::CommitWarnings {
+            empty: Some(true),
+            fresh: Some(true),
+            ..Default::default()
+        };
+        let global = crate::config::GlobalConfig {
+            hide_empty_on_fresh_commit: true,
+            ..Default::default()
+        };
+
+        assert_eq!(render(&config, warnings()), "\u{1b}[0m\u{1b}[34mo");
+        assert_eq!(
+            render_with(&config, warnings(), &global),
+            "\u{1b}[0m\u{1b}[34m@"
+        );
+    }
+}
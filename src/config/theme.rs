@@ -0,0 +1,133 @@
+use std::path::Path;
+
+use jj_cli::command_error::{CommandError, user_error, user_error_with_message};
+use serde_json::{Map, Value};
+
+/// Replaces every `style = "<name>"` reference in `config` with the named style, filling in the
+/// keys the referencing table doesn't set itself.
+///
+/// Named styles come from the `[styles]` table of the `theme` file, overridden by the config's own
+/// `[styles]`. A relative `theme` path is resolved against `base_dir`.
+pub(crate) fn resolve_styles(
+    config: &mut Value,
+    base_dir: Option<&Path>,
+) -> Result<(), CommandError> {
+    let Some(table) = config.as_object_mut() else {
+        return Ok(());
+    };
+
+    let mut styles = match table.get("theme").and_then(Value::as_str) {
+        Some(theme) => load_theme(&base_dir.unwrap_or(Path::new("")).join(theme))?,
+        None => Map::new(),
+    };
+    if let Some(Value::Object(own)) = table.get("styles") {
+        styles.extend(own.clone());
+    }
+
+    for (key, value) in table.iter_mut() {
+        if key != "styles" {
+            apply_styles(value, &styles)?;
+        }
+    }
+    if !styles.is_empty() {
+        table.insert("styles".to_string(), Value::Object(styles));
+    }
+    Ok(())
+}
+
+fn load_theme(path: &Path) -> Result<Map<String, Value>, CommandError> {
+    let theme = std::fs::read_to_string(path).map_err(|err| {
+        user_error_with_message(format!("Failed to read theme `{}`", path.display()), err)
+    })?;
+    let theme: Value = toml::from_str(&theme).map_err(|err| {
+        user_error_with_message(format!("Failed to parse theme `{}`", path.display()), err)
+    })?;
+    match theme.get("styles") {
+        Some(Value::Object(styles)) => Ok(styles.clone()),
+        Some(_) => Err(user_error(format!(
+            "The `styles` in theme `{}` must be a table",
+            path.display()
+        ))),
+        None => Ok(Map::new()),
+    }
+}
+
+fn apply_styles(value: &mut Value, styles: &Map<String, Value>) -> Result<(), CommandError> {
+    match value {
+        Value::Object(table) => {
+            if let Some(Value::String(name)) = table.get("style") {
+                let Some(Value::Object(style)) = styles.get(name) else {
+                    return Err(user_error(format!("Unknown style `{name}`")));
+                };
+                let style = style.clone();
+                table.remove("style");
+                for (key, value) in style {
+                    table.entry(key).or_insert(value);
+                }
+            }
+            for value in table.values_mut() {
+                apply_styles(value, styles)?;
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                apply_styles(value, styles)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn own_styles_override_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("theme.toml"),
+            "[styles]\naccent = { color = \"Green\", bold = true }\nmuted = { color = \"BrightBlack\" }\n",
+        )
+        .unwrap();
+
+        let mut config = serde_json::json!({
+            "theme": "theme.toml",
+            "styles": {"muted": {"color": "White"}},
+            "module": [
+                {"type": "Commit", "style": "accent", "color": "Red"},
+                {"type": "Bookmarks", "untracked": {"style": "muted"}},
+            ],
+        });
+        resolve_styles(&mut config, Some(dir.path())).unwrap();
+        let missing = resolve_styles(
+            &mut serde_json::json!({"theme": "missing.toml"}),
+            Some(dir.path()),
+        );
+        let unknown = resolve_styles(
+            &mut serde_json::json!({"module": [{"style": "nope"}]}),
+            Some(dir.path()),
+        );
+
+        assert_eq!(
+            config["module"][0],
+            serde_json::json!({"type": "Commit", "color": "Red", "bold": true})
+        );
+        assert_eq!(
+            config["module"][1]["untracked"],
+            serde_json::json!({"color": "White"})
+        );
+        assert!(
+            missing
+                .unwrap_err()
+                .error
+                .to_string()
+                .starts_with("Failed to read theme")
+        );
+        assert_eq!(
+            unknown.unwrap_err().error.to_string(),
+            "Unknown style `nope`"
+        );
+    }
+}
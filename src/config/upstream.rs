@@ -0,0 +1,272 @@
+use std::{io::Write, path::Path};
+
+use jj_cli::command_error::CommandError;
+use jj_lib::{object_id::ObjectId as _, ref_name::RefName};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::util::{Color, Style};
+
+/// Prints how far the nearest local bookmark is ahead of and behind its tracked remote bookmark.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Upstream {
+    /// Text that will be printed before the number of commits the bookmark is ahead of its remote.
+    #[serde(default = "default_ahead_symbol")]
+    ahead_symbol: String,
+    /// Text that will be printed before the number of commits the bookmark is behind its remote.
+    #[serde(default = "default_behind_symbol")]
+    behind_symbol: String,
+    /// Controls how the ahead count is rendered.
+    #[serde(default = "default_ahead_style")]
+    ahead: Style,
+    /// Controls how the behind count is rendered.
+    #[serde(default = "default_behind_style")]
+    behind: Style,
+    /// Store the counts on disk and only recompute them when the operation changes.
+    #[serde(default = "default_cache")]
+    cache: bool,
+}
+
+fn default_ahead_symbol() -> String {
+    "↑".to_string()
+}
+
+fn default_behind_symbol() -> String {
+    "↓".to_string()
+}
+
+fn default_ahead_style() -> Style {
+    Style {
+        color: Some(Color::Green),
+        ..Default::default()
+    }
+}
+
+fn default_behind_style() -> Style {
+    Style {
+        color: Some(Color::Red),
+        ..Default::default()
+    }
+}
+
+fn default_cache() -> bool {
+    true
+}
+
+impl Default for Upstream {
+    fn default() -> Self {
+        Self {
+            ahead_symbol: default_ahead_symbol(),
+            behind_symbol: default_behind_symbol(),
+            ahead: default_ahead_style(),
+            behind: default_behind_style(),
+            cache: default_cache(),
+        }
+    }
+}
+
+/// How far a bookmark is ahead of and behind its tracked remote bookmark.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct UpstreamCounts {
+    ahead: usize,
+    behind: usize,
+}
+
+impl Upstream {
+    pub fn print(
+        &self,
+        io: &mut impl Write,
+        data: &crate::JJData,
+        _global: &super::GlobalConfig,
+        prev_style: &mut Option<nu_ansi_term::Style>,
+    ) -> Result<(), CommandError> {
+        let Some(Some(counts)) = &data.upstream else {
+            return Ok(());
+        };
+
+        if counts.ahead > 0 {
+            self.ahead.print(io, default_ahead_style(), prev_style)?;
+            write!(io, "{}{}", self.ahead_symbol, counts.ahead)?;
+        }
+        if counts.behind > 0 {
+            self.behind.print(io, default_behind_style(), prev_style)?;
+            write!(io, "{}{}", self.behind_symbol, counts.behind)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(
+        &self,
+        command_helper: &jj_cli::cli_util::CommandHelper,
+        state: &mut crate::State,
+        data: &mut crate::JJData,
+        global: &super::GlobalConfig,
+    ) -> Result<(), CommandError> {
+        if data.upstream.is_some() {
+            return Ok(());
+        }
+
+        super::bookmarks::Bookmarks::default().parse(command_helper, state, data, global)?;
+        let Some(bookmark) = data
+            .bookmarks
+            .bookmarks
+            .iter()
+            .flatten()
+            .find(|bookmark| !bookmark.name.contains('@'))
+        else {
+            data.upstream = Some(None);
+            return Ok(());
+        };
+        let name = bookmark.name.clone();
+
+        let repo = state.repo(command_helper)?;
+        let op_id = repo.op_id().hex();
+        let cache_path = if self.cache {
+            let workspace_root = state.workspace_helper(command_helper)?.workspace_root();
+            super::util::cache_file("upstream", workspace_root)
+        } else {
+            None
+        };
+
+        let counts = cached_counts(cache_path.as_deref(), &op_id, &name, || {
+            let view = repo.view();
+            let Some(local_id) = view.get_local_bookmark(RefName::new(&name)).as_normal() else {
+                return Ok(None);
+            };
+            let Some(remote_id) = view
+                .bookmarks()
+                .find(|(bookmark_name, _)| bookmark_name.as_str() == name)
+                .and_then(|(_, targets)| {
+                    targets
+                        .remote_refs
+                        .into_iter()
+                        .find(|(remote, remote_ref)| {
+                            remote.as_str() != "git" && remote_ref.is_tracked()
+                        })
+                        .and_then(|(_, remote_ref)| remote_ref.target.as_normal().cloned())
+                })
+            else {
+                return Ok(None);
+            };
+
+            let (local, remote) = (local_id.hex(), remote_id.hex());
+            Ok(Some(UpstreamCounts {
+                ahead: state.count_revset(command_helper, &format!("{remote}..{local}"))?,
+                behind: state.count_revset(command_helper, &format!("{local}..{remote}"))?,
+            }))
+        })?;
+
+        data.upstream = Some(counts);
+        Ok(())
+    }
+}
+
+/// Returns the counts stored for `op_id` and `bookmark`, computing and storing them if the
+/// operation changed since they were cached. Storing them is best effort.
+fn cached_counts(
+    cache_path: Option<&Path>,
+    op_id: &str,
+    bookmark: &str,
+    compute: impl FnOnce() -> Result<Option<UpstreamCounts>, CommandError>,
+) -> Result<Option<UpstreamCounts>, CommandError> {
+    let Some(cache_path) = cache_path else {
+        return compute();
+    };
+
+    if let Ok(cached) = std::fs::read_to_string(cache_path)
+        && let Some(counts) = parse_cache(&cached, op_id, bookmark)
+    {
+        return Ok(counts);
+    }
+
+    let counts = compute()?;
+    if let Some(dir) = cache_path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = std::fs::write(cache_path, format_cache(op_id, bookmark, counts.as_ref()));
+    Ok(counts)
+}
+
+/// Cache files hold the operation id, the bookmark name and the counts, one per line.
+fn format_cache(op_id: &str, bookmark: &str, counts: Option<&UpstreamCounts>) -> String {
+    match counts {
+        Some(counts) => format!("{op_id}\n{bookmark}\n{} {}\n", counts.ahead, counts.behind),
+        None => format!("{op_id}\n{bookmark}\n"),
+    }
+}
+
+fn parse_cache(s: &str, op_id: &str, bookmark: &str) -> Option<Option<UpstreamCounts>> {
+    let mut lines = s.lines();
+    if lines.next()? != op_id || lines.next()? != bookmark {
+        return None;
+    }
+    let Some(counts) = lines.next() else {
+        return Some(None);
+    };
+    let (ahead, behind) = counts.split_once(' ')?;
+    Some(Some(UpstreamCounts {
+        ahead: ahead.parse().ok()?,
+        behind: behind.parse().ok()?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn counts(ahead: usize, behind: usize) -> UpstreamCounts {
+        UpstreamCounts { ahead, behind }
+    }
+
+    #[test]
+    fn counts_are_cached_per_operation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upstream");
+        let computed = Cell::new(0);
+        let render = |op_id: &str| {
+            cached_counts(Some(&path), op_id, "main", || {
+                computed.set(computed.get() + 1);
+                Ok(Some(counts(2, computed.get())))
+            })
+            .unwrap()
+        };
+
+        assert_eq!(render("op1"), Some(counts(2, 1)));
+        assert_eq!(render("op1"), Some(counts(2, 1)));
+        assert_eq!(render("op2"), Some(counts(2, 2)));
+
+        assert_eq!(computed.get(), 2);
+    }
+
+    #[test]
+    fn counts_are_computed_when_the_cache_is_unwritable() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let computed = cached_counts(Some(&file.path().join("upstream")), "op1", "main", || {
+            Ok(Some(counts(1, 0)))
+        });
+
+        assert_eq!(computed.unwrap(), Some(counts(1, 0)));
+    }
+
+    #[test]
+    fn renders_ahead_and_behind() {
+        let data = crate::JJData {
+            upstream: Some(Some(counts(2, 1))),
+            ..Default::default()
+        };
+        let out = crate::config::rendered(|io| {
+            Upstream::default().print(
+                io,
+                &data,
+                &crate::config::GlobalConfig::default(),
+                &mut None,
+            )
+        });
+
+        assert_eq!(out, "\u{1b}[0m\u{1b}[32m↑2\u{1b}[31m↓1");
+    }
+}
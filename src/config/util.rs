@@ -3,10 +3,160 @@ use jj_cli::command_error::CommandError;
 #[cfg(feature = "json-schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::io::Write;
+use std::{
+    borrow::Cow,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use etcetera::BaseStrategy as _;
+use unicode_width::UnicodeWidthChar as _;
+
+/// A per-workspace file in starship-jj's cache directory, e.g. `~/.cache/starship-jj/<kind>/<hash>`.
+pub(crate) fn cache_file(kind: &str, workspace_root: &Path) -> Option<PathBuf> {
+    let cache_dir = etcetera::choose_base_strategy().ok()?.cache_dir();
+    let mut hasher = DefaultHasher::new();
+    workspace_root.hash(&mut hasher);
+    Some(
+        cache_dir
+            .join("starship-jj")
+            .join(kind)
+            .join(format!("{:016x}", hasher.finish())),
+    )
+}
+
+/// Truncates already styled and `escape`d text to `max_width` visible columns, ending it with an
+/// ellipsis. ANSI escape sequences don't count towards the width and are all kept, also the ones
+/// after the cut. Characters doubled by `escape` count once and are never split.
+pub(crate) fn truncate_ansi(text: &str, max_width: usize, escape: Escape) -> Cow<'_, str> {
+    if escaped_width(text, escape) <= max_width {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut width = 0;
+    let mut cut = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            copy_escape_sequence(&mut chars, &mut out);
+            continue;
+        }
+        let doubled = escape.doubles(c) && chars.next_if_eq(&c).is_some();
+        if cut {
+            continue;
+        }
+        let char_width = c.width().unwrap_or_default();
+        if width + char_width + 1 > max_width {
+            cut = true;
+            if max_width > 0 {
+                out.push('…');
+            }
+            continue;
+        }
+        width += char_width;
+        out.push(c);
+        if doubled {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Removes all escape sequences from already styled text.
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            copy_escape_sequence(&mut chars, &mut String::new());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Byte offset of the first visible character in already styled text, i.e. the length of the escape
+/// sequences it starts with.
+pub(crate) fn visible_start(text: &str) -> usize {
+    let mut chars = text.chars().peekable();
+    let mut start = 0;
+    while chars.next_if_eq(&'\x1b').is_some() {
+        let mut sequence = String::new();
+        copy_escape_sequence(&mut chars, &mut sequence);
+        start += 1 + sequence.len();
+    }
+    start
+}
+
+/// Appends `background` after every SGR sequence in already styled text, so neither resets nor
+/// the text's own styles can drop the background.
+pub(crate) fn with_background(text: &str, background: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c == '\x1b' {
+            copy_escape_sequence(&mut chars, &mut out);
+            if out.ends_with('m') {
+                out.push_str(background);
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn visible_width(text: &str) -> usize {
+    escaped_width(text, Escape::None)
+}
+
+/// Like `visible_width`, but counts the characters doubled by `escape` once, as the shell shows them.
+fn escaped_width(text: &str, escape: Escape) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            copy_escape_sequence(&mut chars, &mut String::new());
+        } else {
+            if escape.doubles(c) {
+                chars.next_if_eq(&c);
+            }
+            width += c.width().unwrap_or_default();
+        }
+    }
+    width
+}
+
+/// Copies the rest of a CSI (`ESC [ ... m`) or OSC (`ESC ] ... BEL`) sequence.
+fn copy_escape_sequence(chars: &mut std::iter::Peekable<std::str::Chars>, out: &mut String) {
+    match chars.next_if(|c| *c == '[' || *c == ']') {
+        Some('[') => {
+            out.push('[');
+            for c in chars.by_ref() {
+                out.push(c);
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+        Some(_) => {
+            out.push(']');
+            for c in chars.by_ref() {
+                out.push(c);
+                if c == '\x07' {
+                    break;
+                }
+            }
+        }
+        None => {}
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(try_from = "&str", into = "String")]
+#[serde(try_from = "String", into = "String")]
 pub struct Glob(glob::Pattern);
 impl TryFrom<&str> for Glob {
     type Error = glob::PatternError;
@@ -15,6 +165,14 @@ impl TryFrom<&str> for Glob {
         Ok(Self(Pattern::new(value)?))
     }
 }
+// Configs loaded through the `config` crate only hand out owned strings.
+impl TryFrom<String> for Glob {
+    type Error = glob::PatternError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
 impl From<Glob> for String {
     fn from(value: Glob) -> Self {
         value.0.as_str().to_string()
@@ -27,6 +185,68 @@ impl Glob {
     }
 }
 
+/// A regular expression that has to match the whole text.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Regex {
+    pattern: String,
+    regex: regex::Regex,
+}
+impl TryFrom<String> for Regex {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        let regex = regex::Regex::new(&format!("^(?:{pattern})$"))?;
+        Ok(Self { pattern, regex })
+    }
+}
+impl From<Regex> for String {
+    fn from(value: Regex) -> Self {
+        value.pattern
+    }
+}
+
+impl Regex {
+    pub fn matches(&self, haystack: &str) -> bool {
+        self.regex.is_match(haystack)
+    }
+}
+
+/// How characters with a special meaning to the prompt are escaped.
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// None => [default] Render text as is
+    #[default]
+    None,
+    /// Tmux => Escape `#` and `%` for tmux status lines
+    Tmux,
+    /// ZshPercent => Escape `%` for zsh prompts
+    ZshPercent,
+}
+
+impl Escape {
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Escape::None => Cow::Borrowed(text),
+            Escape::Tmux if text.contains(['#', '%']) => {
+                Cow::Owned(text.replace('#', "##").replace('%', "%%"))
+            }
+            Escape::ZshPercent if text.contains('%') => Cow::Owned(text.replace('%', "%%")),
+            Escape::Tmux | Escape::ZshPercent => Cow::Borrowed(text),
+        }
+    }
+
+    /// Whether `apply` doubles `c`.
+    fn doubles(self, c: char) -> bool {
+        match self {
+            Escape::None => false,
+            Escape::Tmux => c == '#' || c == '%',
+            Escape::ZshPercent => c == '%',
+        }
+    }
+}
+
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct Style {
@@ -40,7 +260,7 @@ pub struct Style {
 }
 
 impl Style {
-    fn merge_with_fallback(&self, fallback: Option<Self>) -> Self {
+    pub(crate) fn merge_with_fallback(&self, fallback: Option<Self>) -> Self {
         let Some(fallback) = fallback else {
             return self.clone();
         };
@@ -122,21 +342,21 @@ impl From<Style> for nu_ansi_term::Style {
 #[allow(clippy::enum_variant_names)]
 pub struct TextAttributess {
     #[serde(default)]
-    bold: Option<bool>,
+    pub bold: Option<bool>,
     #[serde(default)]
-    dimmed: Option<bool>,
+    pub dimmed: Option<bool>,
     #[serde(default)]
-    italic: Option<bool>,
+    pub italic: Option<bool>,
     #[serde(default)]
-    underline: Option<bool>,
+    pub underline: Option<bool>,
     #[serde(default)]
-    blink: Option<bool>,
+    pub blink: Option<bool>,
     #[serde(default)]
-    reverse: Option<bool>,
+    pub reverse: Option<bool>,
     #[serde(default)]
-    hidden: Option<bool>,
+    pub hidden: Option<bool>,
     #[serde(default)]
-    strikethrough: Option<bool>,
+    pub strikethrough: Option<bool>,
 }
 
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
@@ -211,4 +431,73 @@ impl From<Color> for nu_ansi_term::Color {
 // }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_none() {
+        assert_eq!(Escape::None.apply("100% #1"), "100% #1");
+    }
+
+    #[test]
+    fn escape_zsh_percent() {
+        assert_eq!(Escape::ZshPercent.apply("100% %{x} #1"), "100%% %%{x} #1");
+    }
+
+    #[test]
+    fn escape_tmux() {
+        assert_eq!(Escape::Tmux.apply("100% #[fg=red]"), "100%% ##[fg=red]");
+    }
+
+    #[test]
+    fn with_background_survives_resets() {
+        assert_eq!(
+            with_background("a\u{1b}[0m\u{1b}[35mb", "\u{1b}[44m"),
+            "a\u{1b}[0m\u{1b}[44m\u{1b}[35m\u{1b}[44mb"
+        );
+    }
+
+    #[test]
+    fn strip_ansi_keeps_text() {
+        assert_eq!(
+            strip_ansi("\u{1b}[0m\u{1b}[35mmain\u{1b}]8;;https://x\u{7}link\u{1b}[0m "),
+            "mainlink "
+        );
+    }
+
+    #[test]
+    fn visible_start_skips_leading_escapes() {
+        assert_eq!(visible_start("\u{1b}[0m\u{1b}[35mmain\u{1b}[0m"), 9);
+        assert_eq!(visible_start("main"), 0);
+        assert_eq!(visible_start(""), 0);
+    }
+
+    #[test]
+    fn truncate_ansi_ignores_escapes() {
+        let text = "\u{1b}[35mmain\u{1b}[33m origin";
+        assert_eq!(truncate_ansi(text, 11, Escape::None), text);
+        assert_eq!(
+            truncate_ansi(text, 6, Escape::None),
+            "\u{1b}[35mmain\u{1b}[33m …"
+        );
+    }
+
+    #[test]
+    fn truncate_ansi_keeps_escapes_after_the_cut() {
+        let text = "\u{1b}[35mmain\u{1b}[0m \u{1b}[33morigin\u{1b}[0m";
+        assert_eq!(
+            truncate_ansi(text, 3, Escape::None),
+            "\u{1b}[35mma…\u{1b}[0m\u{1b}[33m\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn truncate_ansi_counts_escaped_characters_once() {
+        let text = "100%% #1";
+        assert_eq!(truncate_ansi(text, 7, Escape::ZshPercent), text);
+        assert_eq!(truncate_ansi(text, 5, Escape::ZshPercent), "100%%…");
+        assert_eq!(truncate_ansi(text, 4, Escape::ZshPercent), "100…");
+        assert_eq!(truncate_ansi("1%%2##3", 4, Escape::Tmux), "1%%2…");
+        assert_eq!(truncate_ansi("1%%2##3", 3, Escape::Tmux), "1%%…");
+    }
+}
@@ -0,0 +1,243 @@
+use std::{io::Write, path::PathBuf};
+
+use jj_cli::{
+    cli_util::CommandHelper,
+    command_error::{CommandError, user_error},
+};
+use jj_lib::object_id::ObjectId as _;
+
+/// Longest `timeout` that still renders before starship gives up on the command with its default
+/// `command_timeout`.
+const STARSHIP_COMMAND_TIMEOUT: u64 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// The outcome of a single check.
+#[derive(Debug, PartialEq, Eq)]
+struct Check {
+    status: Status,
+    name: String,
+    message: String,
+}
+
+impl Check {
+    fn new(status: Status, name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks the config and the repo the prompt is rendered for and prints a report. Fails if any
+/// check failed.
+pub fn doctor(
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let checks = run_checks(command_helper, config_path);
+    for check in &checks {
+        let status = match check.status {
+            Status::Ok => "ok",
+            Status::Warning => "warning",
+            Status::Error => "error",
+        };
+        writeln!(io, "{status:<8} {}: {}", check.name, check.message)?;
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Error)
+        .count();
+    match failed {
+        0 => Ok(()),
+        1 => Err(user_error("1 check failed")),
+        _ => Err(user_error(format!("{failed} checks failed"))),
+    }
+}
+
+fn run_checks(command_helper: &CommandHelper, config_path: &Option<PathBuf>) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let config = match crate::load_config_value(config_path) {
+        Ok(value) => {
+            checks.extend(check_globs(&value));
+            match crate::parse_config(value) {
+                Ok(config) => {
+                    let sources = crate::config_sources(config_path, std::env::vars())
+                        .unwrap_or_default()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    checks.push(Check::new(
+                        Status::Ok,
+                        "config",
+                        format!("loaded from {sources}"),
+                    ));
+                    Some(config)
+                }
+                Err(err) => {
+                    checks.push(Check::new(
+                        Status::Error,
+                        "config",
+                        crate::state::format_error_chain(&err),
+                    ));
+                    None
+                }
+            }
+        }
+        Err(err) => {
+            checks.push(Check::new(
+                Status::Error,
+                "config",
+                crate::state::format_error_chain(&err),
+            ));
+            None
+        }
+    };
+    if let Some(config) = &config {
+        checks.push(check_timeout(config.timeout()));
+    }
+
+    let mut state = crate::State::new(!command_helper.global_args().ignore_working_copy);
+    let workspace_helper = match state.workspace_helper(command_helper) {
+        Ok(workspace_helper) => {
+            checks.push(Check::new(
+                Status::Ok,
+                "workspace",
+                format!(
+                    "{} at operation {}",
+                    workspace_helper.workspace_root().display(),
+                    &workspace_helper.repo().op_id().hex()[..12]
+                ),
+            ));
+            Some(workspace_helper)
+        }
+        Err(err) => {
+            let message = crate::state::format_error_chain(&err);
+            let message = if state.is_incompatible() {
+                format!(
+                    "{message} (the repo was written by a jj version this build doesn't support)"
+                )
+            } else {
+                message
+            };
+            checks.push(Check::new(Status::Error, "workspace", message));
+            None
+        }
+    };
+
+    if let (Some(config), Some(workspace_helper)) = (&config, workspace_helper)
+        && let Some(revset) = &config.bookmarks().target_revset
+    {
+        checks.push(match crate::evaluate_revset(workspace_helper, revset) {
+            Ok(commits) => Check::new(
+                Status::Ok,
+                "bookmarks.target_revset",
+                format!("`{revset}` resolves to {} commits", commits.len()),
+            ),
+            Err(err) => Check::new(
+                Status::Error,
+                "bookmarks.target_revset",
+                format!("`{revset}`: {}", crate::state::format_error_chain(&err)),
+            ),
+        });
+    }
+
+    checks
+}
+
+/// Checks every glob in `bookmarks` on its own, so an invalid one can be named.
+fn check_globs(config: &serde_json::Value) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for key in ["exclude", "primary"] {
+        let globs: Vec<&str> = match config.pointer(&format!("/bookmarks/{key}")) {
+            Some(serde_json::Value::Array(globs)) => {
+                globs.iter().filter_map(serde_json::Value::as_str).collect()
+            }
+            Some(serde_json::Value::String(glob)) => vec![glob.as_str()],
+            _ => continue,
+        };
+        let name = format!("bookmarks.{key}");
+        let invalid: Vec<String> = globs
+            .iter()
+            .filter_map(|glob| {
+                glob::Pattern::new(glob)
+                    .err()
+                    .map(|err| format!("`{glob}` is not a valid glob: {err}"))
+            })
+            .collect();
+        if invalid.is_empty() {
+            let message = match globs.len() {
+                1 => "valid".to_string(),
+                count => format!("all {count} globs are valid"),
+            };
+            checks.push(Check::new(Status::Ok, name, message));
+        } else {
+            checks.extend(
+                invalid
+                    .into_iter()
+                    .map(|message| Check::new(Status::Error, name.clone(), message)),
+            );
+        }
+    }
+    checks
+}
+
+fn check_timeout(timeout: Option<u64>) -> Check {
+    match timeout {
+        None => Check::new(Status::Ok, "timeout", "not set"),
+        Some(0) => Check::new(
+            Status::Error,
+            "timeout",
+            "0ms prints the fallback before any module can render",
+        ),
+        Some(timeout) if timeout > STARSHIP_COMMAND_TIMEOUT => Check::new(
+            Status::Warning,
+            "timeout",
+            format!(
+                "{timeout}ms is longer than starship's default command_timeout of \
+                 {STARSHIP_COMMAND_TIMEOUT}ms, raise it or starship kills the prompt first"
+            ),
+        ),
+        Some(timeout) => Check::new(Status::Ok, "timeout", format!("{timeout}ms")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_globs_are_named() {
+        let checks = check_globs(&serde_json::json!({
+            "bookmarks": {"exclude": ["wip/*", "[abc"], "primary": "release/*"}
+        }));
+
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].status, Status::Error);
+        assert_eq!(checks[0].name, "bookmarks.exclude");
+        assert!(checks[0].message.starts_with("`[abc` is not a valid glob"));
+        assert_eq!(
+            checks[1],
+            Check::new(Status::Ok, "bookmarks.primary", "valid")
+        );
+        assert!(check_globs(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn timeout_sanity() {
+        assert_eq!(check_timeout(None).status, Status::Ok);
+        assert_eq!(check_timeout(Some(0)).status, Status::Error);
+        assert_eq!(check_timeout(Some(200)).status, Status::Ok);
+        assert_eq!(check_timeout(Some(1000)).status, Status::Warning);
+    }
+}
@@ -0,0 +1,197 @@
+use std::{collections::HashMap, io::Write, path::Path};
+
+use jj_cli::command_error::{CommandError, user_error, user_error_with_message};
+use jj_lib::backend::{ChangeId, CommitId, MillisSinceEpoch};
+use serde::{Deserialize, Deserializer, de::Error as _};
+
+/// Renders the configured modules against the `JJData` stored in `fixture` without opening a repo.
+///
+/// Commit ids are written in hex, change ids in jj's reverse hex alphabet (`z`-`k`) like `jj log` shows them.
+pub fn render_fixture(
+    config_path: &Option<std::path::PathBuf>,
+    fixture: &Path,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let config = crate::load_config(config_path)?;
+    let fixture = std::fs::read_to_string(fixture)
+        .map_err(|err| user_error_with_message("Failed to read fixture", err))?;
+    let data = parse_fixture(&fixture)?;
+    config.print_data(&data, io)
+}
+
+fn parse_fixture(fixture: &str) -> Result<crate::JJData, CommandError> {
+    serde_json::from_str(fixture).map_err(|err| user_error(format!("Invalid fixture: {err}")))
+}
+
+pub(crate) trait HexId: Sized {
+    fn parse_hex(hex: &str) -> Option<Self>;
+}
+
+impl HexId for CommitId {
+    fn parse_hex(hex: &str) -> Option<Self> {
+        CommitId::try_from_hex(hex)
+    }
+}
+
+impl HexId for ChangeId {
+    fn parse_hex(hex: &str) -> Option<Self> {
+        ChangeId::try_from_reverse_hex(hex)
+    }
+}
+
+fn parse_id<'de, T: HexId, D: Deserializer<'de>>(hex: &str) -> Result<T, D::Error> {
+    T::parse_hex(hex).ok_or_else(|| D::Error::custom(format!("invalid id `{hex}`")))
+}
+
+pub(crate) fn id<'de, T: HexId, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    parse_id::<T, D>(&String::deserialize(deserializer)?)
+}
+
+pub(crate) fn option_id<'de, T: HexId, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|hex| parse_id::<T, D>(&hex))
+        .transpose()
+}
+
+/// An id together with the length of its shortest unique prefix.
+pub(crate) fn option_prefixed_id<'de, T: HexId, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<(T, usize)>, D::Error> {
+    Option::<(String, usize)>::deserialize(deserializer)?
+        .map(|(hex, len)| Ok((parse_id::<T, D>(&hex)?, len)))
+        .transpose()
+}
+
+pub(crate) fn id_map<'de, T, V, D>(deserializer: D) -> Result<HashMap<T, V>, D::Error>
+where
+    T: HexId + std::hash::Hash + Eq,
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    HashMap::<String, V>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(hex, value)| Ok((parse_id::<T, D>(&hex)?, value)))
+        .collect()
+}
+
+/// A time in milliseconds since the epoch, `null` if there was none.
+pub(crate) fn timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<MillisSinceEpoch>>, D::Error> {
+    Ok(Some(
+        Option::<i64>::deserialize(deserializer)?.map(MillisSinceEpoch),
+    ))
+}
+
+pub(crate) fn serialize_timestamp<S: serde::Serializer>(
+    timestamp: &Option<Option<MillisSinceEpoch>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_some(&timestamp.flatten().map(|millis| millis.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(config: &str, fixture: &str) -> String {
+        let config: crate::config::Config = toml::from_str(config).unwrap();
+        crate::config::rendered(|io| config.print_data(&parse_fixture(fixture).unwrap(), io))
+    }
+
+    #[test]
+    fn renders_bookmarks_and_description() {
+        let out = render(
+            r#"
+reset_color = false
+
+[[module]]
+type = "Bookmarks"
+
+[[module]]
+type = "Commit"
+"#,
+            r#"{
+                "bookmarks": {"bookmarks": [
+                    {"name": "main", "distance": 2, "kind": "Tracked", "target": "0123abcd"}
+                ]},
+                "commit": {"desc": "fix the parser"}
+            }"#,
+        );
+        assert_eq!(
+            out,
+            "\u{1b}[0m\u{1b}[35mmain⇡2 \u{1b}[0m\u{1b}[0m\u{1b}[mfix the parser "
+        );
+    }
+
+    #[test]
+    fn renders_without_bookmarks() {
+        let config = |hide_when_current: bool| {
+            format!(
+                "reset_color = false\n\n[[module]]\ntype = \"Bookmarks\"\nhide_when_current = {hide_when_current}\n\n[[module]]\ntype = \"Commit\"\n"
+            )
+        };
+        let fixture = r#"{"commit": {"desc": "fix the parser"}}"#;
+
+        assert_eq!(
+            render(&config(false), fixture),
+            "\u{1b}[0m\u{1b}[mfix the parser "
+        );
+        assert_eq!(
+            render(&config(true), fixture),
+            "\u{1b}[0m\u{1b}[mfix the parser "
+        );
+    }
+
+    #[test]
+    fn renders_warnings_and_diff() {
+        let out = render(
+            r#"
+reset_color = false
+
+[[module]]
+type = "State"
+
+[[module]]
+type = "Metrics"
+"#,
+            r#"{
+                "commit": {
+                    "warnings": {"conflict": true, "empty": false},
+                    "diff": {"files_changed": 3, "lines_added": 10, "lines_removed": 2}
+                }
+            }"#,
+        );
+        assert_eq!(
+            out,
+            "\u{1b}[0m\u{1b}[31m(CONFLICT) \u{1b}[0m\u{1b}[35m[\u{1b}[0m\u{1b}[36m3\u{1b}[35m \u{1b}[0m\u{1b}[32m+10\u{1b}[35m\u{1b}[0m\u{1b}[31m-2\u{1b}[35m] "
+        );
+    }
+
+    #[test]
+    fn parses_ids() {
+        let data = parse_fixture(
+            r#"{
+                "commit": {"change_id": ["zyxwvuts", 2], "commit_id": ["0123abcd", 4]},
+                "last_fetch": 1700000000000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            data.commit.change_id,
+            Some((ChangeId::from_hex("01234567"), 2))
+        );
+        assert_eq!(
+            data.commit.commit_id,
+            Some((CommitId::from_hex("0123abcd"), 4))
+        );
+        assert_eq!(data.last_fetch, Some(Some(MillisSinceEpoch(1700000000000))));
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        assert!(parse_fixture(r#"{"commit": {"commit_id": ["xyz", 1]}}"#).is_err());
+    }
+}
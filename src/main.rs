@@ -1,16 +1,23 @@
-use std::{cmp::Ordering, collections::HashSet, io::Write, path::PathBuf, process::ExitCode};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::OnceLock,
+};
 
 use ::config::Environment;
-use args::{ConfigCommands, CustomCommand, StarshipCommands};
-use config::BookmarkConfig;
+use args::{ConfigCommands, CustomCommand, ErrorFormat, PromptFormat, StarshipCommands};
+use config::{BookmarkConfig, RemoteDistanceBase};
 use etcetera::BaseStrategy as _;
 use jj_cli::{
     cli_util::{CliRunner, CommandHelper, RevisionArg, WorkspaceCommandHelper},
-    command_error::{CommandError, user_error},
+    command_error::{CommandError, CommandErrorKind, user_error, user_error_with_message},
     ui::Ui,
 };
 use jj_lib::{
-    backend::{ChangeId, CommitId},
+    backend::{ChangeId, CommitId, MillisSinceEpoch},
     object_id::ObjectId,
     view::View,
 };
@@ -19,7 +26,10 @@ pub use state::State;
 use unicode_width::UnicodeWidthStr as _;
 
 mod args;
+mod bench;
 mod config;
+mod doctor;
+mod fixture;
 mod state;
 
 pub mod built_info {
@@ -40,19 +50,86 @@ fn starship(
 
     let CustomCommand::Starship(args) = command;
     match args.command {
-        StarshipCommands::Prompt { starship_config } => {
-            print_prompt(command_helper, &starship_config)?
+        StarshipCommands::Prompt {
+            starship_config,
+            error_format,
+            dump_data: dump,
+            format,
+        } => {
+            let result = match (dump, format) {
+                (true, _) => dump_data(
+                    command_helper,
+                    &starship_config,
+                    ui.term_width(),
+                    &mut ui.stdout(),
+                ),
+                (false, PromptFormat::Ansi) => {
+                    print_prompt(command_helper, &starship_config, ui.term_width())
+                }
+                (false, PromptFormat::Json) => print_prompt_json(
+                    command_helper,
+                    &starship_config,
+                    ui.term_width(),
+                    &mut std::io::stdout(),
+                ),
+            };
+            if let (Err(err), ErrorFormat::Json) = (&result, error_format) {
+                eprintln!("{}", error_json(err));
+                let _ = REPORTED_EXIT_CODE.set(error_exit_code(err));
+                return Ok(());
+            }
+            result?
         }
-        StarshipCommands::Config(ConfigCommands::Path) => {
-            let config_dir = get_config_path()?;
-
-            writeln!(ui.stdout(), "{config_dir}")?;
+        StarshipCommands::Watch {
+            starship_config,
+            interval,
+            watch_config,
+        } => watch_prompt(ui, command_helper, &starship_config, interval, watch_config)?,
+        StarshipCommands::Doctor { starship_config } => {
+            doctor::doctor(command_helper, &starship_config, &mut ui.stdout())?
+        }
+        StarshipCommands::Bench {
+            commits,
+            files,
+            runs,
+        } => bench::bench(
+            command_helper.settings(),
+            commits,
+            files,
+            runs,
+            &mut ui.stdout(),
+        )?,
+        StarshipCommands::Bookmarks {
+            starship_config,
+            json,
+        } => print_bookmarks(command_helper, &starship_config, json, &mut ui.stdout())?,
+        StarshipCommands::Render {
+            starship_config,
+            fixture,
+        } => fixture::render_fixture(&starship_config, &fixture, &mut ui.stdout())?,
+        StarshipCommands::Config(ConfigCommands::Path {
+            resolve,
+            starship_config,
+        }) => {
+            if resolve {
+                let _ = dotenvy::dotenv();
+                for source in config_sources(&starship_config, std::env::vars())? {
+                    writeln!(ui.stdout(), "{source}")?;
+                }
+            } else {
+                let config_dir = get_config_path()?;
+
+                writeln!(ui.stdout(), "{config_dir}")?;
+            }
         }
         StarshipCommands::Config(ConfigCommands::Default) => {
             let c = toml::to_string_pretty(&config::Config::default()).map_err(user_error)?;
 
             writeln!(ui.stdout(), "{c}")?;
         }
+        StarshipCommands::Config(ConfigCommands::Check { path }) => {
+            check_config(&path, &mut ui.stdout())?
+        }
     }
 
     Ok(())
@@ -70,96 +147,321 @@ fn get_config_path() -> Result<String, CommandError> {
     Ok(config_dir.to_string())
 }
 
-#[derive(Default)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct JJData {
     bookmarks: BookmarkData,
     commit: CommitData,
+    heads: Option<usize>,
+    #[serde(
+        deserialize_with = "fixture::timestamp",
+        serialize_with = "fixture::serialize_timestamp"
+    )]
+    last_fetch: Option<Option<MillisSinceEpoch>>,
+    upstream: Option<Option<config::UpstreamCounts>>,
+    /// Description of the last operation that wasn't a working copy snapshot.
+    operation: Option<Option<String>>,
+    /// Bookmarks between `trunk()` and the working copy, furthest first.
+    path: Option<Vec<Bookmark>>,
+    /// Number of commits from `trunk()` to the working copy, `None` inside if there is no trunk or
+    /// the working copy doesn't descend from it.
+    trunk_distance: Option<Option<usize>>,
+    /// Number of files in the working copy's tree, `None` inside if there is no working copy commit.
+    file_count: Option<Option<usize>>,
+    /// Number of conflicted commits in the visible history.
+    conflicts: Option<usize>,
 }
 
-#[derive(Default)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct BookmarkData {
     bookmarks: Option<Vec<Bookmark>>,
+    /// Shortest unique prefix length of each bookmark target, only resolved when requested.
+    #[serde(deserialize_with = "fixture::id_map")]
+    target_prefix_lens: HashMap<CommitId, usize>,
+    /// Committer timestamp of each bookmark target in milliseconds since the epoch, only resolved
+    /// when sorting by it.
+    #[serde(deserialize_with = "fixture::id_map")]
+    target_timestamps: HashMap<CommitId, i64>,
+    /// Whether each local bookmark tracks a remote bookmark, only resolved when requested.
+    tracking: HashMap<String, bool>,
+    /// Bookmarks found when passing over the ones on the working copy, only searched for
+    /// `hide_when_current`.
+    above_working_copy: Option<Vec<Bookmark>>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 struct Bookmark {
     name: String,
+    /// Number of commits between the bookmark and the working copy.
     distance: usize,
     kind: BookmarkKind,
+    /// The bookmark is on a descendant of the working copy rather than an ancestor.
+    #[serde(default)]
+    ahead: bool,
+    #[serde(deserialize_with = "fixture::id")]
+    target: CommitId,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize,
+)]
 enum BookmarkKind {
     Tracked,
     Untracked,
 }
 
-#[derive(Default)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct CommitData {
     desc: Option<String>,
     warnings: CommitWarnings,
     diff: Option<CommitDiff>,
     ahead: bool,
+    /// The parent the description was inherited from when `ahead` is set.
+    #[serde(deserialize_with = "fixture::option_id")]
+    desc_source: Option<CommitId>,
+    #[serde(deserialize_with = "fixture::option_prefixed_id")]
     commit_id: Option<(CommitId, usize)>,
+    #[serde(deserialize_with = "fixture::option_prefixed_id")]
     change_id: Option<(ChangeId, usize)>,
+    mine: Option<bool>,
+    /// Committer timestamp of the working copy commit, `None` inside if there is none.
+    #[serde(
+        deserialize_with = "fixture::timestamp",
+        serialize_with = "fixture::serialize_timestamp"
+    )]
+    committed: Option<Option<MillisSinceEpoch>>,
+    author: Option<CommitSignature>,
+    committer: Option<CommitSignature>,
+    /// The working copy commit is the root commit.
+    root: Option<bool>,
+    /// The working copy commit has a description of its own, unlike `desc` which may be inherited.
+    described: Option<bool>,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct CommitSignature {
+    name: String,
+    email: String,
 }
 
-#[derive(Default)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct CommitWarnings {
     hidden: Option<bool>,
     conflict: Option<bool>,
     divergent: Option<bool>,
+    /// Number of visible commits sharing the working copy's change id when it is divergent.
+    divergent_count: Option<usize>,
     immutable: Option<bool>,
     empty: Option<bool>,
+    /// The working copy looks like it was just created by `jj new`.
+    fresh: Option<bool>,
+    /// Where the working copy's conflict comes from, if it has one.
+    conflict_source: Option<ConflictSource>,
+    /// Bookmarks on the immutable heads the working copy descends from.
+    immutable_heads: Option<Vec<String>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum ConflictSource {
+    /// Some conflicted path isn't conflicted in every parent, so the working copy created it (e.g. a merge).
+    Introduced,
+    /// Every parent already has a conflict at each of the conflicted paths.
+    Inherited,
 }
 
-#[derive(Default)]
+impl ConflictSource {
+    /// Compares the conflicted paths of the working copy with those of its parents: a path is
+    /// introduced unless every parent has a conflict there too.
+    fn detect<P: Ord>(conflicts: &BTreeSet<P>, parent_conflicts: &[BTreeSet<P>]) -> Option<Self> {
+        if conflicts.is_empty() || parent_conflicts.is_empty() {
+            return None;
+        }
+        if parent_conflicts
+            .iter()
+            .all(|parent| conflicts.is_subset(parent))
+        {
+            Some(ConflictSource::Inherited)
+        } else {
+            Some(ConflictSource::Introduced)
+        }
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct CommitDiff {
     // files_added : usize,
     // files_removed : usize,
     files_changed: usize,
     lines_added: usize,
     lines_removed: usize,
+    /// The file with the most added and removed lines, only collected if it is rendered.
+    largest_file: Option<LargestFileDiff>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct LargestFileDiff {
+    /// Path of the file in the working copy, relative to the repo root.
+    path: String,
+    /// Share of the file's lines that were added or changed, in percent. `None` for binary,
+    /// added and deleted files, and if it wasn't requested.
+    percent: Option<usize>,
 }
+
 impl CommitDiff {
     fn is_empty(&self) -> bool {
         self.files_changed == 0 && self.lines_added == 0 && self.lines_removed == 0
     }
 }
 
-fn print_prompt(
-    command_helper: &CommandHelper,
+/// A layer the prompt configuration is built from.
+#[derive(Debug, PartialEq, Eq)]
+enum ConfigSource {
+    /// `SJJ__` environment variables overriding individual keys.
+    Environment(Vec<String>),
+    /// A config file.
+    File(PathBuf),
+    /// A config file listed in the `include` of the config file.
+    Include(PathBuf),
+    /// A config piped in on stdin, selected with `--starship-config -`.
+    Stdin,
+    /// The built-in default configuration.
+    Defaults,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Environment(vars) => write!(f, "environment ({})", vars.join(", ")),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Include(path) => write!(f, "included file {}", path.display()),
+            ConfigSource::Stdin => write!(f, "stdin"),
+            ConfigSource::Defaults => write!(f, "built-in defaults"),
+        }
+    }
+}
+
+/// Returns the sources the prompt configuration is built from, highest precedence first.
+fn config_sources(
     config_path: &Option<PathBuf>,
-) -> Result<(), CommandError> {
-    let _ = dotenvy::dotenv();
-    let mut b = ::config::Config::builder();
+    env: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<ConfigSource>, CommandError> {
+    let mut sources = Vec::new();
+
+    let mut vars: Vec<String> = env
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| key.to_uppercase().starts_with("SJJ__"))
+        .collect();
+    if !vars.is_empty() {
+        vars.sort();
+        sources.push(ConfigSource::Environment(vars));
+    }
 
-    if let Some(config_path) = config_path {
-        b = b.add_source(::config::File::new(
-            config_path.to_str().ok_or(CommandError::new(
-                jj_cli::command_error::CommandErrorKind::User,
-                "Invalid Config Path",
-            ))?,
-            ::config::FileFormat::Toml,
-        ));
+    if config_path.as_deref() == Some(Path::new("-")) {
+        sources.push(ConfigSource::Stdin);
+    } else if let Some(config_path) = config_path {
+        push_file_source(&mut sources, config_path.clone());
     } else {
         let config_dir = get_config_path()?;
         if std::fs::exists(&config_dir)? {
-            b = b.add_source(::config::File::new(&config_dir, ::config::FileFormat::Toml));
+            push_file_source(&mut sources, config_dir.into());
         } else {
-            b = b.add_source(
-                ::config::Config::try_from(&config::Config::default())
-                    .expect("Config not serializable?"),
-            );
+            sources.push(ConfigSource::Defaults);
         }
-    };
+    }
 
-    b = b.add_source(
-        Environment::with_prefix("SJJ")
-            .separator("__")
-            .prefix_separator("__")
-            .try_parsing(true),
+    Ok(sources)
+}
+
+/// Adds the config file at `path`, preceded by the files it includes, later includes first.
+fn push_file_source(sources: &mut Vec<ConfigSource>, path: PathBuf) {
+    sources.extend(
+        included_files(&path)
+            .into_iter()
+            .rev()
+            .map(ConfigSource::Include),
     );
+    sources.push(ConfigSource::File(path));
+}
+
+/// The files listed in the `include` of the config file at `path`, resolved against its directory.
+/// A file that can't be read or parsed includes nothing, the error is reported when it's loaded.
+fn included_files(path: &Path) -> Vec<PathBuf> {
+    let Some(table) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|config| toml::from_str::<toml::Table>(&config).ok())
+    else {
+        return Vec::new();
+    };
+    let Some(toml::Value::Array(include)) = table.get("include") else {
+        return Vec::new();
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+    include
+        .iter()
+        .filter_map(toml::Value::as_str)
+        .map(|include| dir.join(include))
+        .collect()
+}
+
+fn load_config(config_path: &Option<PathBuf>) -> Result<config::Config, CommandError> {
+    parse_config(load_config_value(config_path)?)
+}
+
+/// Merges the config sources into a single value with the named styles resolved, without checking
+/// it against the config schema yet.
+fn load_config_value(config_path: &Option<PathBuf>) -> Result<serde_json::Value, CommandError> {
+    let _ = dotenvy::dotenv();
+    let mut b = ::config::Config::builder();
+    let mut config_dir = None;
+
+    for source in config_sources(config_path, std::env::vars())?
+        .into_iter()
+        .rev()
+    {
+        match &source {
+            ConfigSource::Environment(_) => {
+                b = b.add_source(
+                    Environment::with_prefix("SJJ")
+                        .separator("__")
+                        .prefix_separator("__")
+                        .try_parsing(true),
+                );
+            }
+            ConfigSource::File(path) | ConfigSource::Include(path) => {
+                if matches!(source, ConfigSource::File(_)) {
+                    config_dir = path.parent().map(Path::to_path_buf);
+                }
+                b = b.add_source(::config::File::new(
+                    path.to_str().ok_or(CommandError::new(
+                        jj_cli::command_error::CommandErrorKind::User,
+                        "Invalid Config Path",
+                    ))?,
+                    ::config::FileFormat::Toml,
+                ));
+            }
+            ConfigSource::Stdin => {
+                let config = std::io::read_to_string(std::io::stdin()).map_err(|err| {
+                    user_error_with_message("Failed to read Config from stdin", err)
+                })?;
+                b = b.add_source(::config::File::from_str(
+                    &config,
+                    ::config::FileFormat::Toml,
+                ));
+            }
+            ConfigSource::Defaults => {
+                b = b.add_source(
+                    ::config::Config::try_from(&config::Config::default())
+                        .expect("Config not serializable?"),
+                );
+            }
+        }
+    }
 
     let c = b.build().map_err(|err| {
         CommandError::with_message(
@@ -169,28 +471,376 @@ fn print_prompt(
         )
     })?;
 
-    let config: config::Config = c.try_deserialize().map_err(|err| {
+    let mut value: serde_json::Value = c.try_deserialize().map_err(|err| {
         CommandError::with_message(
             jj_cli::command_error::CommandErrorKind::User,
             "Failed to parse Config",
             err,
         )
     })?;
+    config::resolve_styles(&mut value, config_dir.as_deref())?;
+    Ok(value)
+}
+
+fn parse_config(mut value: serde_json::Value) -> Result<config::Config, CommandError> {
+    // `$schema` is only there for editors, and `config` can't express it as a key path.
+    if let Some(table) = value.as_object_mut() {
+        table.remove("$schema");
+    }
+    ::config::Config::try_from(&value)
+        .and_then(|c| c.try_deserialize())
+        .map_err(|err| {
+            CommandError::with_message(
+                jj_cli::command_error::CommandErrorKind::User,
+                "Failed to parse Config",
+                err,
+            )
+        })
+}
+
+/// Loads the config like the prompt does and prints the modules it renders. Errors in a config
+/// file point at the offending line where possible.
+fn check_config(config_path: &Option<PathBuf>, io: &mut impl Write) -> Result<(), CommandError> {
+    let config =
+        load_config(config_path).map_err(|err| locate_config_error(config_path).unwrap_or(err))?;
+    let modules = config.module_types();
+    writeln!(io, "Config is valid, modules:")?;
+    for (index, module) in modules.iter().enumerate() {
+        writeln!(io, "{:>3}. {module}", index + 1)?;
+    }
+    Ok(())
+}
+
+/// Deserializes the config file on its own, for an error with the line it occurred on. `None` if
+/// the file is fine on its own, e.g. because an env override or an included file broke the config.
+fn locate_config_error(config_path: &Option<PathBuf>) -> Option<CommandError> {
+    let path = match config_path {
+        Some(path) if path.as_path() == Path::new("-") => return None,
+        Some(path) => path.clone(),
+        None => get_config_path().ok()?.into(),
+    };
+    let config = std::fs::read_to_string(&path).ok()?;
+    config::locate_error(&config).map(|err| {
+        user_error_with_message(format!("Failed to parse Config `{}`", path.display()), err)
+    })
+}
+
+fn print_prompt(
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    term_width: usize,
+) -> Result<(), CommandError> {
+    let mut config = load_config(config_path)?;
+    config.apply_compact(term_width)?;
+
+    let mut state = State::new(!command_helper.global_args().ignore_working_copy);
+    let mut data = JJData::default();
+
+    config.print(
+        &command_helper,
+        &mut state,
+        &mut data,
+        &mut std::io::stdout(),
+    )?;
+
+    Ok(())
+}
+
+/// Prints the data the configured modules render as JSON instead of the prompt.
+fn print_prompt_json(
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    term_width: usize,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let mut config = load_config(config_path)?;
+    config.apply_compact(term_width)?;
 
     let mut state = State::new(!command_helper.global_args().ignore_working_copy);
     let mut data = JJData::default();
+    config.collect(command_helper, &mut state, &mut data)?;
+
+    writeln!(io, "{}", serde_json::to_string(&data).map_err(user_error)?)?;
+    Ok(())
+}
+
+/// Prints the bookmarks the prompt would show, found the same way the `Bookmarks` module finds them.
+fn print_bookmarks(
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    json: bool,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let config = load_config(config_path)?;
+    let mut state = State::new(!command_helper.global_args().ignore_working_copy);
+    let workspace_helper = state.workspace_helper(command_helper)?;
+    let mut bookmarks = Vec::new();
+    find_parent_bookmarks(
+        workspace_helper,
+        workspace_helper.repo().view(),
+        config.bookmarks(),
+        false,
+        &mut bookmarks,
+    )?;
+    write!(io, "{}", format_bookmarks(&bookmarks, json)?)?;
+    Ok(())
+}
 
-    config.print(&command_helper, &mut state, &mut data)?;
+/// One `name distance` line per bookmark, or a JSON object mapping the names to their distance.
+fn format_bookmarks(bookmarks: &[Bookmark], json: bool) -> Result<String, CommandError> {
+    if json {
+        let map: serde_json::Map<String, serde_json::Value> = bookmarks
+            .iter()
+            .map(|bookmark| {
+                let value = serde_json::json!({
+                    "distance": bookmark.distance,
+                    "kind": bookmark.kind,
+                    "ahead": bookmark.ahead,
+                });
+                (bookmark.name.clone(), value)
+            })
+            .collect();
+        return serde_json::to_string_pretty(&map)
+            .map(|json| json + "\n")
+            .map_err(user_error);
+    }
+    Ok(bookmarks
+        .iter()
+        .map(|bookmark| {
+            let ahead = if bookmark.ahead { " (ahead)" } else { "" };
+            format!("{} {}{ahead}\n", bookmark.name, bookmark.distance)
+        })
+        .collect())
+}
 
+/// Renders the prompt and prints everything that went into it as JSON, for attaching to bug reports.
+fn dump_data(
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    term_width: usize,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let start = std::time::Instant::now();
+    let mut config = load_config(config_path)?;
+    config.apply_compact(term_width)?;
+    let config_time = start.elapsed();
+
+    let mut state = State::new(!command_helper.global_args().ignore_working_copy);
+    let mut data = JJData::default();
+    let mut prompt = Vec::new();
+    config.print(&command_helper, &mut state, &mut data, &mut prompt)?;
+    let render_time = start.elapsed() - config_time;
+
+    let workspace_helper = state.workspace_helper(command_helper)?;
+    let dump = dump_json(
+        &config,
+        &data,
+        &prompt,
+        &workspace_helper.repo().op_id().hex(),
+        workspace_helper.workspace_name().as_str(),
+        [("config", config_time), ("render", render_time)],
+    )?;
+    writeln!(io, "{dump}")?;
     Ok(())
 }
 
+fn dump_json(
+    config: &config::Config,
+    data: &JJData,
+    prompt: &[u8],
+    operation_id: &str,
+    workspace: &str,
+    timing: impl IntoIterator<Item = (&'static str, std::time::Duration)>,
+) -> Result<String, CommandError> {
+    let timing: serde_json::Map<String, serde_json::Value> = timing
+        .into_iter()
+        .map(|(name, duration)| (name.to_string(), (duration.as_secs_f64() * 1000.0).into()))
+        .collect();
+    let dump = serde_json::json!({
+        "version": built_info::PKG_VERSION,
+        "jj_lib_version": env!("JJ_LIB_VERSION"),
+        "operation_id": operation_id,
+        "workspace": workspace,
+        "config": config,
+        "data": data,
+        "prompt": String::from_utf8_lossy(prompt),
+        "timing_ms": timing,
+    });
+    serde_json::to_string_pretty(&dump).map_err(user_error)
+}
+
+/// Serializes `err` as a single line JSON object with its kind and full message.
+fn error_json(err: &CommandError) -> String {
+    let kind = match err.kind {
+        CommandErrorKind::User => "user",
+        CommandErrorKind::Config => "config",
+        CommandErrorKind::Cli => "cli",
+        CommandErrorKind::BrokenPipe => "broken_pipe",
+        CommandErrorKind::Internal => "internal",
+    };
+    serde_json::json!({
+        "kind": kind,
+        "message": state::format_error_chain(err),
+    })
+    .to_string()
+}
+
+/// Exit code of an error that was already reported as JSON, so jj doesn't report it again.
+static REPORTED_EXIT_CODE: OnceLock<u8> = OnceLock::new();
+
+/// The exit code jj uses for errors of this kind.
+fn error_exit_code(err: &CommandError) -> u8 {
+    match err.kind {
+        CommandErrorKind::User | CommandErrorKind::Config => 1,
+        CommandErrorKind::Cli => 2,
+        CommandErrorKind::BrokenPipe => 3,
+        CommandErrorKind::Internal => 255,
+    }
+}
+
+/// Re-renders the prompt every `interval` milliseconds, or only when the config changed if
+/// `watch_config` is set, replacing the previously printed line.
+fn watch_prompt(
+    ui: &mut Ui,
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    interval: u64,
+    watch_config: bool,
+) -> Result<(), CommandError> {
+    if config_path.as_deref() == Some(Path::new("-")) {
+        return Err(user_error(
+            "`watch` re-reads the config for every render and can't read it from stdin",
+        ));
+    }
+    let mut last_modified = None;
+    let mut first = true;
+    let mut lines = 0;
+    loop {
+        let modified = config_modified(config_path)?;
+        if first || !watch_config || modified != last_modified {
+            let mut frame = Vec::new();
+            if let Err(err) = render_frame(command_helper, config_path, ui.term_width(), &mut frame)
+            {
+                frame.clear();
+                write!(frame, "{}", state::format_error_chain(&err))?;
+            }
+            lines = write_frame(&mut ui.stdout(), &frame, lines)?;
+            last_modified = modified;
+            first = false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(interval));
+    }
+}
+
+fn render_frame(
+    command_helper: &CommandHelper,
+    config_path: &Option<PathBuf>,
+    term_width: usize,
+    io: &mut impl Write,
+) -> Result<(), CommandError> {
+    let mut config = load_config(config_path)?;
+    config.apply_compact(term_width)?;
+    let mut state = State::new(!command_helper.global_args().ignore_working_copy);
+    let mut data = JJData::default();
+    config.print_untimed(command_helper, &mut state, &mut data, io)
+}
+
+/// Clears the previous frame, which spanned `previous_lines` line breaks, and prints `frame` in
+/// its place. Returns the line breaks in `frame`, to clear it in turn.
+fn write_frame(io: &mut impl Write, frame: &[u8], previous_lines: usize) -> std::io::Result<usize> {
+    if previous_lines > 0 {
+        write!(io, "\x1b[{previous_lines}F")?;
+    } else {
+        write!(io, "\r")?;
+    }
+    write!(io, "\x1b[J")?;
+    io.write_all(frame)?;
+    io.flush()?;
+    Ok(frame.iter().filter(|&&byte| byte == b'\n').count())
+}
+
+/// The latest modification time of the config files the prompt is built from.
+fn config_modified(
+    config_path: &Option<PathBuf>,
+) -> Result<Option<std::time::SystemTime>, CommandError> {
+    let modified = config_sources(config_path, std::env::vars())?
+        .into_iter()
+        .filter_map(|source| match source {
+            ConfigSource::File(path) | ConfigSource::Include(path) => {
+                std::fs::metadata(path).and_then(|m| m.modified()).ok()
+            }
+            ConfigSource::Environment(_) | ConfigSource::Stdin | ConfigSource::Defaults => None,
+        })
+        .max();
+    Ok(modified)
+}
+
+/// Finds the bookmarks the prompt shows. With `skip_working_copy`, bookmarks on `@` itself are
+/// passed over so the search continues to the next bookmark up.
 fn find_parent_bookmarks(
     workspace_helper: &WorkspaceCommandHelper,
     view: &View,
     config: &BookmarkConfig,
+    skip_working_copy: bool,
     bookmarks: &mut Vec<Bookmark>,
 ) -> Result<(), CommandError> {
+    if let Some(bookmark) =
+        find_nearest_primary_bookmark(workspace_helper, view, config, skip_working_copy)?
+    {
+        bookmarks.push(bookmark);
+    }
+
+    if let Some(bookmark) = find_tug_bookmark(workspace_helper, view, config, skip_working_copy)?
+        && !bookmarks.contains(&bookmark)
+    {
+        bookmarks.push(bookmark);
+    }
+
+    if config.remote_distance_base == RemoteDistanceBase::LocalBookmark {
+        for bookmark in bookmarks.iter_mut() {
+            // A local bookmark that isn't ahead leaves the distance to the working copy.
+            if let Some(distance) = distance_to_local_bookmark(workspace_helper, view, bookmark)?
+                && distance != 0
+            {
+                bookmark.distance = distance;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// How far the local counterpart of the remote `bookmark` is ahead of it, `None` if `bookmark`
+/// isn't a remote bookmark or its local bookmark isn't a descendant.
+fn distance_to_local_bookmark(
+    workspace_helper: &WorkspaceCommandHelper,
+    view: &View,
+    bookmark: &Bookmark,
+) -> Result<Option<usize>, CommandError> {
+    let Some((symbol, _)) = view.all_remote_bookmarks().find(|(symbol, remote_ref)| {
+        format!("{}@{}", symbol.name.as_str(), symbol.remote.as_str()) == bookmark.name
+            && remote_ref
+                .target
+                .added_ids()
+                .any(|id| *id == bookmark.target)
+    }) else {
+        return Ok(None);
+    };
+    let Some(local_id) = view.get_local_bookmark(symbol.name).as_normal() else {
+        return Ok(None);
+    };
+    count_range(
+        workspace_helper,
+        format!("{}::{}", bookmark.target.hex(), local_id.hex()),
+    )
+}
+
+fn find_tug_bookmark(
+    workspace_helper: &WorkspaceCommandHelper,
+    view: &View,
+    config: &BookmarkConfig,
+    skip_working_copy: bool,
+) -> Result<Option<Bookmark>, CommandError> {
     // First check if @ has bookmarks
     let wc_revs =
         workspace_helper.parse_revset(&Ui::null(), &RevisionArg::from("@".to_string()))?;
@@ -198,30 +848,35 @@ fn find_parent_bookmarks(
         .evaluate_to_commit_ids()?
         .collect::<Result<Vec<_>, _>>()?;
 
-    if let Some(wc_id) = wc_ids.first() {
+    if let Some(wc_id) = wc_ids.first()
+        && !skip_working_copy
+    {
         // Check for bookmarks on @
         let wc_bookmarks = collect_bookmarks_for_commit(wc_id, view, config, 0);
         if let Some(bookmark) = select_bookmark(wc_bookmarks) {
-            bookmarks.push(bookmark);
-            return Ok(());
+            return Ok(Some(bookmark));
         }
     }
 
     let mut selected_bookmark = None;
 
     // No bookmarks on @, use tug logic to find tracked target
-    let revs = workspace_helper.parse_revset(
-        &Ui::null(),
-        &RevisionArg::from("latest((heads(::@- & bookmarks())))".to_string()),
-    )?;
-
-    let commit_ids: Vec<CommitId> = revs
-        .evaluate_to_commit_ids()?
-        .collect::<Result<Vec<_>, _>>()?;
+    let commit_ids = match &config.target_revset {
+        Some(revset) => evaluate_revset(workspace_helper, revset).map_err(|err| {
+            let mut error = user_error_with_message(
+                format!("Failed to evaluate bookmarks.target_revset `{revset}`"),
+                err.error.to_string(),
+            );
+            error.hints = err.hints;
+            error
+        })?,
+        None => evaluate_revset(workspace_helper, DEFAULT_TARGET_REVSET)?,
+    };
 
     if let Some(target_id) = commit_ids.first()
         && let Some(distance) = distance_to_working_copy(workspace_helper, target_id)?
         && distance <= config.search_depth
+        && (distance != 0 || !skip_working_copy)
     {
         for bookmark in collect_bookmarks_for_commit(target_id, view, config, distance)
             .into_iter()
@@ -231,25 +886,157 @@ fn find_parent_bookmarks(
         }
     }
 
-    if let Some(bookmark) = find_nearest_untracked_bookmark(workspace_helper, view, config)? {
+    if let Some(bookmark) =
+        find_nearest_untracked_bookmark(workspace_helper, view, config, skip_working_copy)?
+    {
         choose_bookmark(&mut selected_bookmark, bookmark);
     }
 
-    if let Some(bookmark) = selected_bookmark {
-        bookmarks.push(bookmark);
+    if selected_bookmark.is_none() {
+        selected_bookmark = find_nearest_descendant_bookmark(workspace_helper, view, config)?;
     }
 
-    Ok(())
+    Ok(selected_bookmark)
+}
+
+/// Finds the nearest tracked bookmark on a descendant of the working copy, e.g. after editing an
+/// older commit.
+fn find_nearest_descendant_bookmark(
+    workspace_helper: &WorkspaceCommandHelper,
+    view: &View,
+    config: &BookmarkConfig,
+) -> Result<Option<Bookmark>, CommandError> {
+    let revs = workspace_helper.parse_revset(
+        &Ui::null(),
+        &RevisionArg::from("roots((@:: ~ @) & bookmarks())".to_string()),
+    )?;
+
+    let mut selected_bookmark = None;
+    for target_id in revs.evaluate_to_commit_ids()? {
+        let target_id = target_id?;
+        let Some(distance) = count_range(workspace_helper, format!("@::{}", target_id.hex()))?
+        else {
+            continue;
+        };
+        if distance > config.search_depth {
+            continue;
+        }
+        for bookmark in collect_bookmarks_for_commit(&target_id, view, config, distance)
+            .into_iter()
+            .filter(|bookmark| bookmark.kind == BookmarkKind::Tracked)
+        {
+            choose_bookmark(
+                &mut selected_bookmark,
+                Bookmark {
+                    ahead: true,
+                    ..bookmark
+                },
+            );
+        }
+    }
+    Ok(selected_bookmark)
+}
+
+/// Selects the commit whose bookmarks are shown when `@` has none.
+const DEFAULT_TARGET_REVSET: &str = "latest((heads(::@- & bookmarks())))";
+
+fn evaluate_revset(
+    workspace_helper: &WorkspaceCommandHelper,
+    revset: &str,
+) -> Result<Vec<CommitId>, CommandError> {
+    let revs =
+        workspace_helper.parse_revset(&Ui::null(), &RevisionArg::from(revset.to_string()))?;
+    Ok(revs
+        .evaluate_to_commit_ids()?
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Finds the nearest ancestor bookmark matching the configured `primary` glob.
+fn find_nearest_primary_bookmark(
+    workspace_helper: &WorkspaceCommandHelper,
+    view: &View,
+    config: &BookmarkConfig,
+    skip_working_copy: bool,
+) -> Result<Option<Bookmark>, CommandError> {
+    if config.primary.is_none() {
+        return Ok(None);
+    }
+
+    let mut candidates = Vec::new();
+    // Matched before measuring the distance, which takes a revset per bookmark.
+    let is_candidate =
+        |name: &str| bookmark_is_primary(config, name) && !bookmark_excluded(config, name);
+
+    for (name, target) in view.local_bookmarks() {
+        if !is_candidate(name.as_str()) {
+            continue;
+        }
+        for commit_id in target.added_ids() {
+            let Some(distance) = distance_to_working_copy(workspace_helper, commit_id)? else {
+                continue;
+            };
+            candidates.push(Bookmark {
+                name: name.as_str().to_string(),
+                distance,
+                kind: BookmarkKind::Tracked,
+                ahead: false,
+                target: commit_id.clone(),
+            });
+        }
+    }
+
+    for (symbol, remote_ref) in view.all_remote_bookmarks() {
+        let name = format!("{}@{}", symbol.name.as_str(), symbol.remote.as_str());
+        if !is_candidate(&name) {
+            continue;
+        }
+        let kind = if remote_ref.is_tracked() {
+            BookmarkKind::Tracked
+        } else {
+            BookmarkKind::Untracked
+        };
+        for commit_id in remote_ref.target.added_ids() {
+            let Some(distance) = distance_to_working_copy(workspace_helper, commit_id)? else {
+                continue;
+            };
+            candidates.push(Bookmark {
+                name: name.clone(),
+                distance,
+                kind,
+                ahead: false,
+                target: commit_id.clone(),
+            });
+        }
+    }
+
+    candidates.retain(|bookmark| bookmark.distance != 0 || !skip_working_copy);
+    Ok(select_primary_bookmark(config, candidates))
+}
+
+fn select_primary_bookmark(
+    config: &BookmarkConfig,
+    candidates: impl IntoIterator<Item = Bookmark>,
+) -> Option<Bookmark> {
+    select_bookmark(candidates.into_iter().filter(|bookmark| {
+        bookmark.distance <= config.search_depth
+            && bookmark_is_primary(config, &bookmark.name)
+            && !bookmark_excluded(config, &bookmark.name)
+    }))
 }
 
 fn distance_to_working_copy(
     workspace_helper: &WorkspaceCommandHelper,
     target_id: &CommitId,
 ) -> Result<Option<usize>, CommandError> {
-    let distance_revs = workspace_helper.parse_revset(
-        &Ui::null(),
-        &RevisionArg::from(format!("{}::@", target_id.hex())),
-    )?;
+    count_range(workspace_helper, format!("{}::@", target_id.hex()))
+}
+
+/// Number of commits between the ends of the `from::to` `range`, `None` if they aren't connected.
+fn count_range(
+    workspace_helper: &WorkspaceCommandHelper,
+    range: String,
+) -> Result<Option<usize>, CommandError> {
+    let distance_revs = workspace_helper.parse_revset(&Ui::null(), &RevisionArg::from(range))?;
     let count = distance_revs
         .evaluate_to_commit_ids()?
         .collect::<Result<Vec<_>, _>>()?
@@ -259,7 +1046,7 @@ fn distance_to_working_copy(
         return Ok(None);
     }
 
-    // Subtract one because target::@ includes target itself.
+    // Subtract one because from::to includes both ends.
     Ok(Some(count.saturating_sub(1)))
 }
 
@@ -267,6 +1054,7 @@ fn find_nearest_untracked_bookmark(
     workspace_helper: &WorkspaceCommandHelper,
     view: &View,
     config: &BookmarkConfig,
+    skip_working_copy: bool,
 ) -> Result<Option<Bookmark>, CommandError> {
     let mut selected_bookmark = None;
 
@@ -284,7 +1072,7 @@ fn find_nearest_untracked_bookmark(
             let Some(distance) = distance_to_working_copy(workspace_helper, commit_id)? else {
                 continue;
             };
-            if distance > config.search_depth {
+            if distance > config.search_depth || distance == 0 && skip_working_copy {
                 continue;
             }
 
@@ -294,6 +1082,8 @@ fn find_nearest_untracked_bookmark(
                     name: name.clone(),
                     distance,
                     kind: BookmarkKind::Untracked,
+                    ahead: false,
+                    target: commit_id.clone(),
                 },
             );
         }
@@ -319,12 +1109,16 @@ fn collect_bookmarks_for_commit(
                 name: name_str.to_string(),
                 distance,
                 kind: BookmarkKind::Tracked,
+                ahead: false,
+                target: commit_id.clone(),
             });
             local_names.insert(name_str.to_string());
         }
     }
 
-    // Remote bookmarks (if no local with same name)
+    // Remote bookmarks (if no local with same name), sorted by their rendered `name@remote` so
+    // the first one is the same on every run.
+    let mut remote = BTreeMap::new();
     for (symbol, remote_ref) in view.all_remote_bookmarks() {
         if remote_ref.target.added_ids().any(|id| id == commit_id)
             && !local_names.contains(symbol.name.as_str())
@@ -336,14 +1130,17 @@ fn collect_bookmarks_for_commit(
                 } else {
                     BookmarkKind::Untracked
                 };
-                bookmarks.push(Bookmark {
-                    name,
-                    distance,
-                    kind,
-                });
+                remote.insert(name, kind);
             }
         }
     }
+    bookmarks.extend(remote.into_iter().map(|(name, kind)| Bookmark {
+        name,
+        distance,
+        kind,
+        ahead: false,
+        target: commit_id.clone(),
+    }));
 
     bookmarks
 }
@@ -358,6 +1155,19 @@ fn bookmark_excluded(_config: &BookmarkConfig, _name: &str) -> bool {
     false
 }
 
+#[cfg(not(feature = "json-schema"))]
+fn bookmark_is_primary(config: &BookmarkConfig, name: &str) -> bool {
+    config
+        .primary
+        .as_ref()
+        .is_some_and(|glob| glob.matches(name))
+}
+
+#[cfg(feature = "json-schema")]
+fn bookmark_is_primary(_config: &BookmarkConfig, _name: &str) -> bool {
+    false
+}
+
 fn select_bookmark(bookmarks: impl IntoIterator<Item = Bookmark>) -> Option<Bookmark> {
     bookmarks.into_iter().min_by(compare_bookmarks)
 }
@@ -377,51 +1187,115 @@ fn compare_bookmarks(left: &Bookmark, right: &Bookmark) -> Ordering {
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod bookmark_selection_tests {
     use super::*;
+    use crate::config::bookmark;
 
-    fn bookmark(name: &str, distance: usize, kind: BookmarkKind) -> Bookmark {
+    fn untracked(name: &str, distance: usize) -> Bookmark {
         Bookmark {
-            name: name.to_string(),
-            distance,
-            kind,
+            kind: BookmarkKind::Untracked,
+            ..bookmark(name, distance)
         }
     }
 
     #[test]
     fn selects_nearest_bookmark() {
-        let selected = select_bookmark([
-            bookmark("main", 5, BookmarkKind::Tracked),
-            bookmark("topic@origin", 2, BookmarkKind::Untracked),
-        ]);
+        let selected = select_bookmark([bookmark("main", 5), untracked("topic@origin", 2)]);
 
-        assert_eq!(
-            selected,
-            Some(bookmark("topic@origin", 2, BookmarkKind::Untracked))
-        );
+        assert_eq!(selected, Some(untracked("topic@origin", 2)));
     }
 
     #[test]
     fn selects_tracked_bookmark_when_distance_ties() {
-        let selected = select_bookmark([
-            bookmark("topic@origin", 3, BookmarkKind::Untracked),
-            bookmark("main", 3, BookmarkKind::Tracked),
-        ]);
+        let selected = select_bookmark([untracked("topic@origin", 3), bookmark("main", 3)]);
 
-        assert_eq!(selected, Some(bookmark("main", 3, BookmarkKind::Tracked)));
+        assert_eq!(selected, Some(bookmark("main", 3)));
     }
 
     #[test]
     fn selects_lexicographic_bookmark_when_kind_and_distance_tie() {
-        let selected = select_bookmark([
-            bookmark("zeta@origin", 3, BookmarkKind::Untracked),
-            bookmark("alpha@origin", 3, BookmarkKind::Untracked),
-        ]);
+        let selected = select_bookmark([untracked("zeta@origin", 3), untracked("alpha@origin", 3)]);
+
+        assert_eq!(selected, Some(untracked("alpha@origin", 3)));
+    }
+
+    #[test]
+    fn remote_bookmarks_at_same_commit_are_sorted() {
+        use jj_lib::{
+            op_store::{RefTarget, RemoteRef, RemoteRefState},
+            ref_name::{RefName, RemoteName},
+        };
+
+        let commit_id = CommitId::from_hex("0123");
+        let mut view = View::new(jj_lib::op_store::View::make_root(CommitId::from_hex("00")));
+        for (name, remote) in [
+            ("topic", "upstream"),
+            ("main", "origin"),
+            ("feature", "origin"),
+        ] {
+            view.set_remote_bookmark(
+                RefName::new(name).to_remote_symbol(RemoteName::new(remote)),
+                RemoteRef {
+                    target: RefTarget::normal(commit_id.clone()),
+                    state: RemoteRefState::New,
+                },
+            );
+        }
 
+        let names = |bookmarks: Vec<Bookmark>| -> Vec<String> {
+            bookmarks
+                .into_iter()
+                .map(|bookmark| bookmark.name)
+                .collect()
+        };
+        let config = BookmarkConfig::default();
+        let first = collect_bookmarks_for_commit(&commit_id, &view, &config, 0);
         assert_eq!(
-            selected,
-            Some(bookmark("alpha@origin", 3, BookmarkKind::Untracked))
+            names(first.clone()),
+            ["feature@origin", "main@origin", "topic@upstream"]
+        );
+        for _ in 0..10 {
+            let again = collect_bookmarks_for_commit(&commit_id, &view, &config, 0);
+            assert_eq!(again, first);
+            assert_eq!(
+                select_bookmark(again)
+                    .map(|bookmark| bookmark.name)
+                    .as_deref(),
+                Some("feature@origin")
+            );
+        }
+    }
+
+    #[test]
+    fn selects_primary_bookmark_over_closer_bookmark() {
+        let config = BookmarkConfig {
+            primary: Some("release/*".try_into().unwrap()),
+            ..Default::default()
+        };
+
+        let selected = select_primary_bookmark(
+            &config,
+            [
+                bookmark("main", 1),
+                bookmark("release/1.0", 4),
+                bookmark("release/0.9", 7),
+            ],
         );
+
+        assert_eq!(selected, Some(bookmark("release/1.0", 4)));
+    }
+
+    #[test]
+    fn no_primary_bookmark_without_match() {
+        let config = BookmarkConfig {
+            primary: Some("release/*".try_into().unwrap()),
+            ..Default::default()
+        };
+
+        let selected = select_primary_bookmark(&config, [bookmark("main", 1)]);
+
+        assert_eq!(selected, None);
     }
 }
 
@@ -437,6 +1311,7 @@ fn main() -> ExitCode {
     ));
     let clirunner = clirunner.add_subcommand(starship);
     let e = clirunner.run();
+    let e = REPORTED_EXIT_CODE.get().copied().unwrap_or(e);
     let elapsed = start.elapsed();
     if print_timing {
         print!("{elapsed:?} ");
@@ -449,6 +1324,30 @@ fn print_ansi_truncated(
     io: &mut impl Write,
     name: &str,
     surround_with_quotes: bool,
+    escape: config::util::Escape,
+) -> Result<(), CommandError> {
+    print_truncated(max_length, io, name, surround_with_quotes, escape, false)
+}
+
+/// Like `print_ansi_truncated`, but a truncated `name` ends on a whole word unless its first word
+/// alone is too long.
+fn print_ansi_truncated_words(
+    max_length: Option<usize>,
+    io: &mut impl Write,
+    name: &str,
+    surround_with_quotes: bool,
+    escape: config::util::Escape,
+) -> Result<(), CommandError> {
+    print_truncated(max_length, io, name, surround_with_quotes, escape, true)
+}
+
+fn print_truncated(
+    max_length: Option<usize>,
+    io: &mut impl Write,
+    name: &str,
+    surround_with_quotes: bool,
+    escape: config::util::Escape,
+    whole_words: bool,
 ) -> Result<(), CommandError> {
     let maybe_quotes = if surround_with_quotes { "\"" } else { "" };
 
@@ -460,18 +1359,285 @@ fn print_ansi_truncated(
                 .take_while(|i| name[..*i].width() < max_len)
                 .last()
                 .unwrap_or_default();
+            let mut truncated = &name[..ansi_max_len];
+            if whole_words && !name[ansi_max_len..].starts_with(char::is_whitespace) {
+                let words = truncated
+                    .rfind(char::is_whitespace)
+                    .map(|end| truncated[..end].trim_end())
+                    .unwrap_or_default();
+                if !words.is_empty() {
+                    truncated = words;
+                }
+            }
 
             write!(
                 io,
                 "{}{}…{}",
                 maybe_quotes,
-                &name[..ansi_max_len],
+                escape.apply(truncated),
                 maybe_quotes
             )?;
         }
         _ => {
-            write!(io, "{maybe_quotes}{name}{maybe_quotes}")?;
+            write!(io, "{maybe_quotes}{}{maybe_quotes}", escape.apply(name))?;
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn provided_config_loads() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("starship-jj.toml");
+        let config = load_config(&Some(path)).unwrap();
+
+        assert_eq!(
+            config.module_types(),
+            ["Bookmarks", "Commit", "State", "Metrics"]
+        );
+    }
+
+    #[test]
+    fn env_overrides_are_listed_first() {
+        let sources = config_sources(
+            &Some(PathBuf::from("/tmp/starship-jj.toml")),
+            [
+                ("HOME".to_string(), "/home/me".to_string()),
+                ("SJJ__MODULE_SEPARATOR".to_string(), "|".to_string()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            sources,
+            vec![
+                ConfigSource::Environment(vec!["SJJ__MODULE_SEPARATOR".to_string()]),
+                ConfigSource::File(PathBuf::from("/tmp/starship-jj.toml")),
+            ]
+        );
+    }
+
+    #[test]
+    fn includes_are_layered_over_the_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("local")).unwrap();
+        std::fs::write(
+            dir.join("starship-jj.toml"),
+            r#"
+include = ["local/override.toml"]
+module_separator = "|"
+
+[[module]]
+type = "Commit"
+
+[[module]]
+type = "Metrics"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("local/override.toml"),
+            "[[module]]\ntype = \"Symbol\"\n",
+        )
+        .unwrap();
+        let config_path = Some(dir.join("starship-jj.toml"));
+        let sources = config_sources(&config_path, []).unwrap();
+        let value = load_config_value(&config_path).unwrap();
+
+        assert_eq!(
+            sources,
+            vec![
+                ConfigSource::Include(dir.join("local/override.toml")),
+                ConfigSource::File(dir.join("starship-jj.toml")),
+            ]
+        );
+        assert_eq!(value["module_separator"], "|");
+        assert_eq!(value["module"], serde_json::json!([{"type": "Symbol"}]));
+    }
+
+    #[test]
+    fn check_lists_modules_or_points_at_the_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("starship-jj.toml");
+        let check = |config: &str| {
+            std::fs::write(&path, config).unwrap();
+            let mut out = Vec::new();
+            check_config(&Some(path.clone()), &mut out)
+                .map(|()| String::from_utf8(out).unwrap())
+                .map_err(|err| crate::state::format_error_chain(&err))
+        };
+        let valid = check("[[module]]\ntype = \"Commit\"\n[[module]]\ntype = \"Metrics\"\n");
+        let invalid = check("module_separator = \"|\"\ntimeout = \"soon\"\n");
+
+        assert_eq!(
+            valid.unwrap(),
+            "Config is valid, modules:\n  1. Commit\n  2. Metrics\n"
+        );
+        let invalid = invalid.unwrap_err();
+        assert!(invalid.contains("line 2"), "{invalid}");
+        assert!(invalid.contains("expected u64"), "{invalid}");
+    }
+
+    #[test]
+    fn theme_styles_resolve_in_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("theme.toml"),
+            "[styles]\naccent = { color = \"Green\" }\n",
+        )
+        .unwrap();
+        let config_path = dir.path().join("starship-jj.toml");
+        std::fs::write(
+            &config_path,
+            "theme = \"theme.toml\"\nreset_color = false\n\n[[module]]\ntype = \"Commit\"\nstyle = \"accent\"\n",
+        )
+        .unwrap();
+
+        let config = load_config(&Some(config_path));
+
+        let data = JJData {
+            commit: CommitData {
+                desc: Some("wip".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let out = crate::config::rendered(|io| config.unwrap().print_data(&data, io));
+        assert_eq!(out, "\u{1b}[0m\u{1b}[32mwip ");
+    }
+}
+
+#[cfg(test)]
+mod truncation_tests {
+    use super::*;
+
+    #[test]
+    fn escapes_after_truncation() {
+        let out = crate::config::rendered(|io| {
+            print_ansi_truncated(
+                Some(6),
+                io,
+                "100% done",
+                false,
+                config::util::Escape::ZshPercent,
+            )
+        });
+
+        assert_eq!(out, "100%% …");
+    }
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use super::*;
+
+    #[test]
+    fn watch_frame_replaces_previous_line() {
+        let out = crate::config::rendered(|io| {
+            let lines = write_frame(io, b"main fix things", 0)?;
+            write_frame(io, b"main fix more", lines).map(drop)
+        });
+
+        assert_eq!(out, "\r\x1b[Jmain fix things\r\x1b[Jmain fix more");
+    }
+
+    #[test]
+    fn watch_frame_replaces_every_previous_line() {
+        let out = crate::config::rendered(|io| {
+            let lines = write_frame(io, b"main\nfix things", 0)?;
+            assert_eq!(lines, 1);
+            write_frame(io, b"main\nfix more", lines).map(drop)
+        });
+
+        assert_eq!(out, "\r\x1b[Jmain\nfix things\x1b[1F\x1b[Jmain\nfix more");
+    }
+
+    #[test]
+    fn config_modified_tracks_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("starship-jj.toml");
+        std::fs::write(&path, "").unwrap();
+
+        let modified = config_modified(&Some(path.clone())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(modified.is_some());
+        assert_eq!(config_modified(&Some(path)).unwrap(), None);
+    }
+}
+
+#[cfg(test)]
+mod error_format_tests {
+    use super::*;
+
+    #[test]
+    fn json_error_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("starship-jj.toml");
+        let err = load_config(&Some(path)).unwrap_err();
+
+        let json: serde_json::Value = serde_json::from_str(&error_json(&err)).unwrap();
+
+        assert_eq!(json["kind"], "user");
+        assert!(
+            json["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Failed to parse Config")
+        );
+        assert_eq!(error_exit_code(&err), 1);
+    }
+}
+
+#[cfg(test)]
+mod dump_tests {
+    use super::*;
+
+    #[test]
+    fn dump_contains_everything_collected() {
+        let data = JJData {
+            commit: CommitData {
+                desc: Some("fix the parser".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let dump = dump_json(
+            &config::Config::default(),
+            &data,
+            b"prompt",
+            "abc123",
+            "default",
+            [("render", std::time::Duration::from_millis(5))],
+        )
+        .unwrap();
+        let dump: serde_json::Value = serde_json::from_str(&dump).unwrap();
+
+        let mut keys: Vec<&str> = dump
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "config",
+                "data",
+                "jj_lib_version",
+                "operation_id",
+                "prompt",
+                "timing_ms",
+                "version",
+                "workspace"
+            ]
+        );
+        assert_eq!(dump["data"]["commit"]["desc"], "fix the parser");
+        assert_eq!(dump["timing_ms"]["render"], 5.0);
+    }
+}
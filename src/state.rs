@@ -1,25 +1,44 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use jj_cli::{
-    cli_util::{CommandHelper, WorkspaceCommandHelper},
-    command_error::CommandError,
+    cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper},
+    command_error::{CommandError, internal_error},
     diff_util::{DiffStatOptions, DiffStats, get_copy_records},
     ui::Ui,
 };
 use jj_lib::{
-    backend::CommitId,
+    backend::{CommitId, Signature},
     commit::Commit,
+    conflicts::{MaterializedTreeValue, materialize_tree_value},
     copies::CopyRecords,
     fileset::FilesetExpression,
+    matchers::Matcher,
     merged_tree::MergedTree,
-    repo::{ReadonlyRepo, Repo},
+    repo::{ReadonlyRepo, Repo, StoreLoadError},
+    repo_path::RepoPath,
+    store::Store,
 };
 use pollster::FutureExt;
 
 type Result<T> = std::result::Result<T, CommandError>;
 
+/// The user configured in jj's `user.name` and `user.email` settings.
+#[derive(Clone, Debug, Default)]
+pub struct User {
+    pub name: String,
+    pub email: String,
+}
+
+impl User {
+    /// Whether `signature` belongs to this user. Always false when no email is configured.
+    pub fn is_author(&self, signature: &Signature) -> bool {
+        !self.email.is_empty() && signature.email.eq_ignore_ascii_case(&self.email)
+    }
+}
+
 pub struct State {
     snapshot: bool,
+    incompatible: bool,
     workspace_helper: Option<WorkspaceCommandHelper>,
     repo: Option<Arc<ReadonlyRepo>>,
     commit_id: Option<Option<CommitId>>,
@@ -27,12 +46,19 @@ pub struct State {
     parent_commits: Option<Vec<Commit>>,
     tree: Option<Option<MergedTree>>,
     parent_tree: Option<Option<MergedTree>>,
+    user: Option<User>,
+    revset_counts: HashMap<String, usize>,
+    revset_contains_working_copy: HashMap<String, bool>,
+    /// Diff stats against the parents that are being computed in the background, together with
+    /// whether they detect renames.
+    diff_stats_prefetch: Option<(bool, std::thread::JoinHandle<Result<DiffStats>>)>,
 }
 
 impl State {
     pub fn new(snapshot: bool) -> Self {
         Self {
             snapshot,
+            incompatible: false,
             workspace_helper: Default::default(),
             repo: Default::default(),
             commit_id: Default::default(),
@@ -40,22 +66,44 @@ impl State {
             parent_commits: Default::default(),
             tree: Default::default(),
             parent_tree: Default::default(),
+            user: Default::default(),
+            revset_counts: Default::default(),
+            revset_contains_working_copy: Default::default(),
+            diff_stats_prefetch: None,
         }
     }
 
+    /// Load the workspace without snapshotting the working copy, reusing the tree recorded by the
+    /// last snapshot. Has no effect once the workspace is loaded.
+    pub fn skip_snapshot(&mut self) {
+        self.snapshot = false;
+    }
+
+    /// Whether the workspace was loaded after snapshotting the working copy.
+    pub fn has_snapshotted(&self) -> bool {
+        self.snapshot && self.workspace_helper.is_some()
+    }
+
     fn load_workspace(&mut self, command_helper: &CommandHelper) -> Result<()> {
         if self.workspace_helper.is_some() {
             return Ok(());
         }
         let helper = if self.snapshot {
-            command_helper.workspace_helper(&Ui::null())?
+            command_helper.workspace_helper(&Ui::null())
         } else {
-            command_helper.workspace_helper_no_snapshot(&Ui::null())?
+            command_helper.workspace_helper_no_snapshot(&Ui::null())
         };
+        let helper =
+            helper.inspect_err(|err| self.incompatible = is_incompatible_repo_error(err))?;
         self.workspace_helper = Some(helper);
         Ok(())
     }
 
+    /// Whether loading the workspace failed because the repo uses a format this jj version doesn't support.
+    pub fn is_incompatible(&self) -> bool {
+        self.incompatible
+    }
+
     pub fn workspace_helper(
         &mut self,
         command_helper: &CommandHelper,
@@ -85,6 +133,65 @@ impl State {
         Ok(repo.clone())
     }
 
+    /// Number of commits in `revset`, evaluated at most once per render.
+    pub fn count_revset(&mut self, command_helper: &CommandHelper, revset: &str) -> Result<usize> {
+        if let Some(count) = self.revset_counts.get(revset) {
+            return Ok(*count);
+        }
+        let revset_expression = self
+            .workspace_helper(command_helper)?
+            .parse_revset(&Ui::null(), &RevisionArg::from(revset.to_string()))?;
+        let mut count = 0;
+        for commit_id in revset_expression.evaluate_to_commit_ids()? {
+            commit_id?;
+            count += 1;
+        }
+        self.revset_counts.insert(revset.to_string(), count);
+        Ok(count)
+    }
+
+    /// Whether the working copy commit is in `revset`, evaluated at most once per render.
+    pub fn working_copy_in(
+        &mut self,
+        command_helper: &CommandHelper,
+        revset: &str,
+    ) -> Result<bool> {
+        if let Some(contains) = self.revset_contains_working_copy.get(revset) {
+            return Ok(*contains);
+        }
+        let Some(commit_id) = self.commit_id(command_helper)?.clone() else {
+            return Ok(false);
+        };
+        let revset_expression = self
+            .workspace_helper(command_helper)?
+            .parse_revset(&Ui::null(), &RevisionArg::from(revset.to_string()))?;
+        let contains = revset_expression.evaluate()?.containing_fn()(&commit_id)?;
+        self.revset_contains_working_copy
+            .insert(revset.to_string(), contains);
+        Ok(contains)
+    }
+
+    pub fn load_user(&mut self, command_helper: &CommandHelper) -> Result<()> {
+        if self.user.is_some() {
+            return Ok(());
+        }
+        let settings = self.workspace_helper(command_helper)?.settings();
+        let user = User {
+            name: settings.user_name().to_string(),
+            email: settings.user_email().to_string(),
+        };
+        self.user = Some(user);
+        Ok(())
+    }
+
+    pub fn user(&mut self, command_helper: &CommandHelper) -> Result<&User> {
+        self.load_user(command_helper)?;
+        let Some(w) = self.user.as_ref() else {
+            unreachable!()
+        };
+        Ok(w)
+    }
+
     pub fn load_commit_id(&mut self, command_helper: &CommandHelper) -> Result<()> {
         if self.commit_id.is_some() {
             return Ok(());
@@ -130,6 +237,14 @@ impl State {
         Ok(w)
     }
 
+    /// Whether the working copy commit is the root commit, which has no parents and an empty tree.
+    pub fn is_root(&mut self, command_helper: &CommandHelper) -> Result<bool> {
+        Ok(self
+            .commit(command_helper)?
+            .as_ref()
+            .is_some_and(|commit| commit.id() == commit.store().root_commit_id()))
+    }
+
     pub fn load_parent_commits(&mut self, command_helper: &CommandHelper) -> Result<()> {
         if self.parent_commits.is_some() {
             return Ok(());
@@ -195,7 +310,59 @@ impl State {
         Ok(w)
     }
 
-    pub fn diff_stats(&mut self, command_helper: &CommandHelper) -> Result<Option<DiffStats>> {
+    /// Starts computing the diff stats between the working copy and its parents on another thread,
+    /// so the other modules can load their data meanwhile. `diff_stats` picks up the result.
+    pub fn prefetch_diff_stats(
+        &mut self,
+        command_helper: &CommandHelper,
+        rename_detection: bool,
+    ) -> Result<()> {
+        if self.diff_stats_prefetch.is_some() {
+            return Ok(());
+        }
+        self.load_parent_tree(command_helper)?;
+        self.load_tree(command_helper)?;
+        let store = self.repo(command_helper)?.store().clone();
+        let (Some(Some(commit)), Some(Some(tree)), Some(Some(parent_tree))) =
+            (&self.commit, &self.tree, &self.parent_tree)
+        else {
+            return Ok(());
+        };
+
+        let (commit, tree, parent_tree) = (commit.clone(), tree.clone(), parent_tree.clone());
+        let handle = std::thread::spawn(move || {
+            calculate_diff_stats(
+                &store,
+                &commit,
+                commit.parent_ids(),
+                &parent_tree,
+                &tree,
+                rename_detection,
+            )
+        });
+        self.diff_stats_prefetch = Some((rename_detection, handle));
+        Ok(())
+    }
+
+    /// Computes the diff stats between the working copy and its parents.
+    ///
+    /// Copy records are only collected when `rename_detection` is set, so renames count as an
+    /// added and a removed file otherwise.
+    pub fn diff_stats(
+        &mut self,
+        command_helper: &CommandHelper,
+        rename_detection: bool,
+    ) -> Result<Option<DiffStats>> {
+        if let Some((prefetched_renames, handle)) = self.diff_stats_prefetch.take() {
+            let prefetched = handle
+                .join()
+                .map_err(|_| internal_error("The diff stats thread panicked"))?;
+            // The modules share the diff stats they parsed, so nothing else waits for a prefetch
+            // with other settings.
+            if prefetched_renames == rename_detection {
+                return prefetched.map(Some);
+            }
+        }
         self.load_parent_tree(command_helper)?;
         self.load_tree(command_helper)?;
 
@@ -213,22 +380,70 @@ impl State {
             return Ok(None);
         };
 
-        let matcher = FilesetExpression::all().to_matcher();
-        let mut copy_records = CopyRecords::default();
-        for parent in commit.parent_ids() {
-            let records = get_copy_records(store, parent, commit.id(), &matcher)?;
-            copy_records.add_records(records)?;
-        }
-        let tree_diff = parent_tree.diff_stream_with_copies(tree, &matcher, &copy_records);
-        let stats = DiffStats::calculate(
-            repo.store(),
-            tree_diff,
-            &DiffStatOptions::default(),
-            jj_lib::conflicts::ConflictMarkerStyle::Diff,
+        calculate_diff_stats(
+            store,
+            commit,
+            commit.parent_ids(),
+            parent_tree,
+            tree,
+            rename_detection,
         )
-        .block_on()?;
+        .map(Some)
+    }
+
+    /// Computes the diff stats between `base` and the working copy, e.g. everything changed since
+    /// a bookmark.
+    pub fn diff_stats_since(
+        &mut self,
+        command_helper: &CommandHelper,
+        base: &CommitId,
+        rename_detection: bool,
+    ) -> Result<Option<DiffStats>> {
+        self.load_tree(command_helper)?;
+
+        let repo = self.repo(command_helper)?;
+        let store = repo.store();
+
+        let Some(Some(commit)) = self.commit.as_ref() else {
+            return Ok(None);
+        };
+        let Some(Some(tree)) = self.tree.as_ref() else {
+            return Ok(None);
+        };
+        let base_tree = store.get_commit(base)?.tree()?;
 
-        Ok(Some(stats))
+        calculate_diff_stats(
+            store,
+            commit,
+            std::slice::from_ref(base),
+            &base_tree,
+            tree,
+            rename_detection,
+        )
+        .map(Some)
+    }
+
+    /// Number of lines of the file at `path` in the working copy. `None` if there is no regular
+    /// text file at `path`, e.g. because it was deleted or is binary.
+    pub fn line_count(
+        &mut self,
+        command_helper: &CommandHelper,
+        path: &RepoPath,
+    ) -> Result<Option<usize>> {
+        self.load_tree(command_helper)?;
+
+        let repo = self.repo(command_helper)?;
+        let Some(Some(tree)) = self.tree.as_ref() else {
+            return Ok(None);
+        };
+        let value = tree.path_value(path)?;
+        let MaterializedTreeValue::File(mut file) =
+            materialize_tree_value(repo.store(), path, value).block_on()?
+        else {
+            return Ok(None);
+        };
+        let contents = file.read_all(path).block_on()?;
+        Ok(count_lines(&contents))
     }
 
     pub fn commit_is_empty(&mut self, command_helper: &CommandHelper) -> Result<Option<bool>> {
@@ -245,3 +460,315 @@ impl State {
         Ok(Some(tree == parent_tree))
     }
 }
+
+/// Diffs `from` against `commit`'s tree, following copies from each of `sources` to `commit` if
+/// `rename_detection` is set.
+fn calculate_diff_stats(
+    store: &Arc<Store>,
+    commit: &Commit,
+    sources: &[CommitId],
+    from: &MergedTree,
+    tree: &MergedTree,
+    rename_detection: bool,
+) -> Result<DiffStats> {
+    let matcher = FilesetExpression::all().to_matcher();
+    let copy_records = if rename_detection {
+        collect_copy_records(store, sources, commit.id(), matcher.as_ref())?
+    } else {
+        CopyRecords::default()
+    };
+    let tree_diff = from.diff_stream_with_copies(tree, &matcher, &copy_records);
+    let stats = DiffStats::calculate(
+        store,
+        tree_diff,
+        &DiffStatOptions::default(),
+        jj_lib::conflicts::ConflictMarkerStyle::Diff,
+    )
+    .block_on()?;
+    Ok(stats)
+}
+
+/// Collects the copies from each of `sources` to `commit`, one source per thread.
+fn collect_copy_records(
+    store: &Store,
+    sources: &[CommitId],
+    commit: &CommitId,
+    matcher: &dyn Matcher,
+) -> Result<CopyRecords> {
+    let per_source = parallel_map(sources, |source| {
+        get_copy_records(store, source, commit, matcher).map(|records| records.collect::<Vec<_>>())
+    })?;
+    // Merge in source order so the result matches a serial collection.
+    let mut copy_records = CopyRecords::default();
+    for records in per_source {
+        copy_records.add_records(records?)?;
+    }
+    Ok(copy_records)
+}
+
+/// Number of lines in `contents`, counting a last line without a newline. `None` if `contents`
+/// contains a NUL byte, i.e. looks binary.
+fn count_lines(contents: &[u8]) -> Option<usize> {
+    if contents.contains(&0) {
+        return None;
+    }
+    let newlines = contents.iter().filter(|&&byte| byte == b'\n').count();
+    Some(newlines + usize::from(!contents.is_empty() && !contents.ends_with(b"\n")))
+}
+
+fn is_incompatible_repo_error(err: &CommandError) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err.error.as_ref());
+    while let Some(err) = source {
+        if let Some(StoreLoadError::UnsupportedType { .. }) = err.downcast_ref() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Formats an error together with all of its sources on a single line.
+pub fn format_error_chain(err: &CommandError) -> String {
+    let mut message = err.error.to_string();
+    let mut source = err.error.source();
+    while let Some(err) = source {
+        message.push_str(&format!(": {err}"));
+        source = err.source();
+    }
+    message
+}
+
+/// Runs `f` for every item, in parallel when there is more than one, and returns the results in
+/// input order. Fails if any of the threads panicked.
+fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Result<Vec<R>> {
+    if items.len() <= 1 {
+        return Ok(items.iter().map(f).collect());
+    }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items.iter().map(|item| scope.spawn(|| f(item))).collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .map_err(|_| internal_error("A worker thread panicked"))
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use jj_cli::command_error::{internal_error_with_message, user_error};
+    use jj_lib::{
+        backend::{CopyId, TreeValue},
+        merge::Merge,
+        merged_tree::MergedTreeBuilder,
+        repo_path::RepoPathBuf,
+        settings::UserSettings,
+        transaction::Transaction,
+        workspace::Workspace,
+    };
+
+    use super::*;
+
+    /// Writes a commit on top of `parents` whose tree holds exactly `files`.
+    fn write_commit(
+        tx: &mut Transaction,
+        parents: Vec<CommitId>,
+        files: &[(&str, &str)],
+    ) -> Commit {
+        let store = tx.repo().store().clone();
+        let mut builder = MergedTreeBuilder::new(store.empty_merged_tree_id());
+        for (path, contents) in files {
+            let path = RepoPathBuf::from_internal_string(*path).unwrap();
+            let id = store
+                .write_file(&path, &mut contents.as_bytes())
+                .block_on()
+                .unwrap();
+            builder.set_or_remove(
+                path,
+                Merge::normal(TreeValue::File {
+                    id,
+                    executable: false,
+                    copy_id: CopyId::placeholder(),
+                }),
+            );
+        }
+        let tree_id = builder.write_tree(&store).unwrap();
+        tx.repo_mut().new_commit(parents, tree_id).write().unwrap()
+    }
+
+    fn signature(email: &str) -> Signature {
+        Signature {
+            name: "Someone".to_string(),
+            email: email.to_string(),
+            timestamp: jj_lib::backend::Timestamp {
+                timestamp: jj_lib::backend::MillisSinceEpoch(0),
+                tz_offset: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn user_is_author_of_own_commit() {
+        let user = User {
+            name: "Me".to_string(),
+            email: "Me@Example.com".to_string(),
+        };
+
+        assert!(user.is_author(&signature("me@example.com")));
+        assert!(!user.is_author(&signature("you@example.com")));
+    }
+
+    #[test]
+    fn user_without_email_is_never_author() {
+        assert!(!User::default().is_author(&signature("")));
+    }
+
+    #[test]
+    fn detects_incompatible_repo_error() {
+        let err = internal_error_with_message(
+            "This version of the jj binary doesn't support this type of repo",
+            StoreLoadError::UnsupportedType {
+                store: "commit",
+                store_type: "future".to_string(),
+            },
+        );
+
+        assert!(is_incompatible_repo_error(&err));
+        assert_eq!(
+            format_error_chain(&err),
+            "This version of the jj binary doesn't support this type of repo: \
+             Unsupported commit backend type 'future'"
+        );
+    }
+
+    #[test]
+    fn other_errors_are_not_incompatible() {
+        let err = user_error("There is no jj repo in \".\"");
+
+        assert!(!is_incompatible_repo_error(&err));
+    }
+
+    #[test]
+    fn parallel_map_matches_serial_order() {
+        let parents = [30u64, 0, 20, 10];
+        let f = |delay: &u64| {
+            std::thread::sleep(std::time::Duration::from_millis(*delay));
+            vec![*delay, delay + 1]
+        };
+
+        let serial: Vec<_> = parents.iter().map(f).collect();
+        let parallel = parallel_map(&parents, f).unwrap();
+
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn parallel_map_reports_panics() {
+        let result = parallel_map(&[1, 0], |n: &u32| {
+            assert_ne!(*n, 0, "boom");
+            *n
+        });
+
+        assert_eq!(
+            format_error_chain(&result.unwrap_err()),
+            "A worker thread panicked"
+        );
+    }
+
+    #[test]
+    fn merge_copy_records_match_serial_collection() {
+        let settings =
+            UserSettings::from_config(jj_lib::config::StackedConfig::with_defaults()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (_workspace, repo) = Workspace::init_internal_git(&settings, dir.path()).unwrap();
+        let mut tx = repo.start_transaction();
+        let (a, b) = ("a\nb\nc\nd\ne\n", "1\n2\n3\n4\n5\n");
+
+        // Each parent of the merge has one of the files the merge renames.
+        let root = repo.store().root_commit_id().clone();
+        let base = write_commit(&mut tx, vec![root], &[("a.txt", a), ("b.txt", b)]);
+        let left = write_commit(
+            &mut tx,
+            vec![base.id().clone()],
+            &[("a2.txt", a), ("b.txt", b)],
+        );
+        let right = write_commit(
+            &mut tx,
+            vec![base.id().clone()],
+            &[("a.txt", a), ("b2.txt", b)],
+        );
+        let merge = write_commit(
+            &mut tx,
+            vec![left.id().clone(), right.id().clone()],
+            &[("a2.txt", a), ("b2.txt", b)],
+        );
+        let repo = tx.commit("create merge").unwrap();
+        let store = repo.store();
+        let matcher = FilesetExpression::all().to_matcher();
+
+        let mut serial = CopyRecords::default();
+        for parent in merge.parent_ids() {
+            serial
+                .add_records(get_copy_records(store, parent, merge.id(), matcher.as_ref()).unwrap())
+                .unwrap();
+        }
+        let parallel =
+            collect_copy_records(store, merge.parent_ids(), merge.id(), matcher.as_ref()).unwrap();
+
+        assert_eq!(
+            parallel.iter().collect::<Vec<_>>(),
+            serial.iter().collect::<Vec<_>>()
+        );
+        let renames: Vec<_> = parallel
+            .iter()
+            .map(|record| {
+                (
+                    record.source.as_internal_file_string(),
+                    record.target.as_internal_file_string(),
+                )
+            })
+            .collect();
+        assert_eq!(renames, [("b.txt", "b2.txt"), ("a.txt", "a2.txt")]);
+    }
+
+    #[test]
+    fn renames_count_as_edits_only_with_rename_detection() {
+        let settings =
+            UserSettings::from_config(jj_lib::config::StackedConfig::with_defaults()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (_workspace, repo) = Workspace::init_internal_git(&settings, dir.path()).unwrap();
+        let mut tx = repo.start_transaction();
+        let root = repo.store().root_commit_id().clone();
+        let parent = write_commit(&mut tx, vec![root], &[("old.txt", "a\nb\nc\nd\ne\n")]);
+        let commit = write_commit(
+            &mut tx,
+            vec![parent.id().clone()],
+            &[("new.txt", "a\nb\nc\nd\nf\n")],
+        );
+        let repo = tx.commit("rename a file").unwrap();
+
+        let counts = |rename_detection| {
+            let stats = calculate_diff_stats(
+                repo.store(),
+                &commit,
+                commit.parent_ids(),
+                &parent.tree().unwrap(),
+                &commit.tree().unwrap(),
+                rename_detection,
+            )
+            .unwrap();
+            (
+                stats.entries().len(),
+                stats.count_total_added(),
+                stats.count_total_removed(),
+            )
+        };
+
+        assert_eq!(counts(true), (1, 1, 1));
+        assert_eq!(counts(false), (2, 5, 5));
+    }
+}
@@ -0,0 +1,35 @@
+use std::process::Command;
+
+#[test]
+fn tiny_benchmark_renders_the_prompt() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+        .current_dir(dir.path())
+        .env("JJ_CONFIG", dir.path().join("no-config.toml"))
+        .env("JJ_USER", "Test User")
+        .env("JJ_EMAIL", "test@example.com")
+        .args([
+            "starship",
+            "bench",
+            "--commits",
+            "3",
+            "--files",
+            "2",
+            "--runs",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        out.starts_with("commits: 3, files: 2, runs: 1\nmin: "),
+        "{out}"
+    );
+    assert_eq!(out.lines().count(), 6);
+}
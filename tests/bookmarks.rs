@@ -0,0 +1,136 @@
+use std::{path::Path, process::Command};
+
+fn jj(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+        .current_dir(repo)
+        .env("JJ_CONFIG", repo.join("no-config.toml"))
+        .env("JJ_USER", "Test User")
+        .env("JJ_EMAIL", "test@example.com")
+        .env_remove("STARSHIP_JJ_CONFIG")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn bookmarks_subcommand_lists_nearest_bookmarks() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = repo.join("starship-jj.toml");
+    std::fs::write(&config, "").unwrap();
+    let config = config.to_str().unwrap();
+
+    jj(repo, &["git", "init"]);
+    jj(repo, &["commit", "-m", "first"]);
+    jj(repo, &["bookmark", "create", "main", "-r", "@-"]);
+    jj(repo, &["commit", "-m", "second"]);
+    jj(repo, &["commit", "-m", "third"]);
+    let text = jj(
+        repo,
+        &["starship", "bookmarks", "--starship-config", config],
+    );
+    let json = jj(
+        repo,
+        &[
+            "starship",
+            "bookmarks",
+            "--json",
+            "--starship-config",
+            config,
+        ],
+    );
+
+    assert_eq!(text, "main 3\n");
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"main": {"distance": 3, "kind": "Tracked", "ahead": false}})
+    );
+}
+
+#[test]
+fn primary_bookmark_skips_nearer_and_excluded_bookmarks() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = repo.join("starship-jj.toml");
+    std::fs::write(
+        &config,
+        "[bookmarks]\nprimary = \"release-*\"\nexclude = [\"release-old*\"]\n",
+    )
+    .unwrap();
+    let config = config.to_str().unwrap();
+
+    jj(repo, &["git", "init"]);
+    jj(repo, &["commit", "-m", "first"]);
+    jj(repo, &["bookmark", "create", "release-1", "-r", "@-"]);
+    jj(repo, &["commit", "-m", "second"]);
+    jj(repo, &["bookmark", "create", "release-old", "-r", "@-"]);
+    jj(repo, &["commit", "-m", "third"]);
+    jj(repo, &["bookmark", "create", "feature", "-r", "@-"]);
+    let text = jj(
+        repo,
+        &["starship", "bookmarks", "--starship-config", config],
+    );
+
+    assert_eq!(text, "release-1 3\nfeature 1\n");
+}
+
+#[test]
+fn remote_distance_from_local_bookmark() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    std::fs::create_dir_all(&repo).unwrap();
+    let config = |base: &str| {
+        let path = dir.path().join(format!("{base}.toml"));
+        std::fs::write(
+            &path,
+            format!("[bookmarks]\nprimary = \"main@origin\"\nremote_distance_base = \"{base}\"\n"),
+        )
+        .unwrap();
+        path.to_str().unwrap().to_string()
+    };
+    let (head, local) = (config("Head"), config("LocalBookmark"));
+
+    let origin = dir.path().join("origin.git");
+    let status = Command::new("git")
+        .args(["init", "--quiet", "--bare"])
+        .arg(&origin)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    jj(&repo, &["git", "init"]);
+    jj(
+        &repo,
+        &["git", "remote", "add", "origin", origin.to_str().unwrap()],
+    );
+    jj(&repo, &["commit", "-m", "pushed"]);
+    jj(&repo, &["bookmark", "create", "main", "-r", "@-"]);
+    jj(&repo, &["git", "push", "--bookmark", "main", "--allow-new"]);
+    let in_sync = jj(
+        &repo,
+        &["starship", "bookmarks", "--starship-config", &local],
+    );
+    // `main` is two commits ahead of `main@origin`, the working copy one more.
+    jj(&repo, &["commit", "-m", "second"]);
+    jj(&repo, &["commit", "-m", "third"]);
+    jj(&repo, &["bookmark", "set", "main", "-r", "@-"]);
+    let from_head = jj(
+        &repo,
+        &["starship", "bookmarks", "--starship-config", &head],
+    );
+    let from_local = jj(
+        &repo,
+        &["starship", "bookmarks", "--starship-config", &local],
+    );
+
+    // Without a lag the distance to the working copy is kept.
+    assert_eq!(in_sync, "main@origin 1\nmain 1\n");
+    assert_eq!(from_head, "main@origin 3\nmain 1\n");
+    assert_eq!(from_local, "main@origin 2\nmain 1\n");
+}
@@ -0,0 +1,689 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+fn jj(repo: &Path, args: &[&str], stdin: &str) -> String {
+    jj_with_env(repo, args, stdin, &[])
+}
+
+fn jj_with_env(repo: &Path, args: &[&str], stdin: &str, env: &[(&str, &str)]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+        .current_dir(repo)
+        .envs(env.iter().copied())
+        .env("JJ_CONFIG", repo.join("no-config.toml"))
+        .env("JJ_USER", "Test User")
+        .env("JJ_EMAIL", "test@example.com")
+        .env_remove("STARSHIP_JJ_CONFIG")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn config_is_read_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "piped config"], "");
+    let prompt = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        r#"
+reset_color = false
+
+[[module]]
+type = "Commit"
+color = "Red"
+"#,
+    );
+
+    assert_eq!(prompt, "\u{1b}[0m\u{1b}[31mpiped config ");
+}
+
+#[test]
+fn locator_names_working_copy_from_nearest_bookmark() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["commit", "-m", "base"], "");
+    jj(repo, &["bookmark", "create", "stack", "-r", "@-"], "");
+    jj(repo, &["commit", "-m", "on top"], "");
+    let prompt = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        r#"
+reset_color = false
+
+[[module]]
+type = "Locator"
+"#,
+    );
+
+    assert_eq!(prompt, "\u{1b}[0m\u{1b}[35mstack+2 ");
+}
+
+#[test]
+fn module_errors_only_affect_failed_module() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = |on_module_error: &str| {
+        format!(
+            r#"
+reset_color = false
+on_module_error = "{on_module_error}"
+
+[bookmarks]
+target_revset = "nope("
+
+[[module]]
+type = "Commit"
+color = "Red"
+
+[[module]]
+type = "Bookmarks"
+
+[[module]]
+type = "Commit"
+color = "Blue"
+"#
+        )
+    };
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "desc"], "");
+    let prompt = |on_module_error| {
+        jj(
+            repo,
+            &["starship", "prompt", "--starship-config", "-"],
+            &config(on_module_error),
+        )
+    };
+    let skip = prompt("Skip");
+    let glyph = prompt("Glyph");
+
+    assert_eq!(skip, "\u{1b}[0m\u{1b}[31mdesc \u{1b}[34mdesc ");
+    assert_eq!(glyph, "\u{1b}[0m\u{1b}[31mdesc ⚠ \u{1b}[34mdesc ");
+}
+
+#[test]
+fn watch_ignores_the_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = repo.join("starship-jj.toml");
+    std::fs::write(&config, "timeout = 0\n\n[[module]]\ntype = \"Commit\"\n").unwrap();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "watched"], "");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+        .current_dir(repo)
+        .env("JJ_CONFIG", repo.join("no-config.toml"))
+        .args(["starship", "watch", "--interval", "50", "--starship-config"])
+        .arg(&config)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    let running = child.try_wait().unwrap().is_none();
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(running, "watch exited after the timeout");
+    let frames = String::from_utf8_lossy(&output.stdout);
+    assert!(frames.matches("watched").count() > 1, "{frames:?}");
+}
+
+#[test]
+fn watch_clears_every_line_of_the_previous_frame() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = repo.join("starship-jj.toml");
+    std::fs::write(
+        &config,
+        "reset_color = false\n\n[[module]]\ntype = \"Commit\"\n\n[[module]]\ntype = \"LineBreak\"\n\n[[module]]\ntype = \"Commit\"\n",
+    )
+    .unwrap();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "watched"], "");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+        .current_dir(repo)
+        .env("JJ_CONFIG", repo.join("no-config.toml"))
+        .env("NO_COLOR", "1")
+        .args(["starship", "watch", "--interval", "50", "--starship-config"])
+        .arg(&config)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let frames = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        frames.starts_with("\r\x1b[Jwatched\nwatched "),
+        "{frames:?}"
+    );
+    // Later frames move back up over the line break of the one before.
+    assert!(
+        frames.contains("watched \x1b[1F\x1b[Jwatched\nwatched "),
+        "{frames:?}"
+    );
+}
+
+#[test]
+fn json_format_prints_collected_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "json prompt"], "");
+    std::fs::write(repo.join("file"), "one\ntwo\n").unwrap();
+    let output = jj(
+        repo,
+        &[
+            "starship",
+            "prompt",
+            "--format",
+            "json",
+            "--starship-config",
+            "-",
+        ],
+        r#"
+[[module]]
+type = "Commit"
+
+[[module]]
+type = "Metrics"
+"#,
+    );
+
+    let data: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert!(!output.contains('\u{1b}'), "{output:?}");
+    assert_eq!(data["commit"]["desc"], "json prompt\n");
+    assert_eq!(
+        data["commit"]["diff"],
+        serde_json::json!({"files_changed": 1, "lines_added": 2, "lines_removed": 0, "largest_file": null})
+    );
+}
+
+#[test]
+fn json_errors_are_reported_once_with_exit_code() {
+    for extra in [&[][..], &["--dump-data"]] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+            .current_dir(std::env::temp_dir())
+            .args([
+                "starship",
+                "prompt",
+                "--error-format",
+                "json",
+                "--starship-config",
+                "-",
+            ])
+            .args(extra)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"bogus = [").unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert_eq!(output.status.code(), Some(1), "{extra:?}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let error: serde_json::Value = serde_json::from_str(&stderr).unwrap();
+        assert_eq!(error["kind"], "user");
+    }
+}
+
+#[test]
+fn json_format_conflicts_with_dump_data() {
+    let output = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+        .args(["starship", "prompt", "--format", "json", "--dump-data"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot be used with"), "{stderr}");
+}
+
+#[test]
+fn rapid_renders_snapshot_only_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let cache = repo.join("cache");
+    let env = [("XDG_CACHE_HOME", cache.to_str().unwrap())];
+    let config = r#"
+reset_color = false
+snapshot_min_interval_ms = 60000
+
+[[module]]
+type = "Metrics"
+template = "{changed}"
+"#;
+    let snapshots = || {
+        jj(
+            repo,
+            &[
+                "--ignore-working-copy",
+                "op",
+                "log",
+                "--no-graph",
+                "-T",
+                "description ++ \"\\n\"",
+            ],
+            "",
+        )
+        .lines()
+        .filter(|line| *line == "snapshot working copy")
+        .count()
+    };
+
+    jj(repo, &["git", "init"], "");
+    std::fs::write(repo.join("first"), "1\n").unwrap();
+    let first = jj_with_env(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        config,
+        &env,
+    );
+    let after_first = snapshots();
+    std::fs::write(repo.join("second"), "2\n").unwrap();
+    let second = jj_with_env(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        config,
+        &env,
+    );
+    let after_second = snapshots();
+
+    assert_eq!(first, "\u{1b}[0m\u{1b}[35m\u{1b}[0m\u{1b}[36m1\u{1b}[35m ");
+    assert_eq!(second, first);
+    assert_eq!(after_second, after_first);
+}
+
+#[test]
+fn largest_file_shows_share_of_lines_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    std::fs::create_dir_all(repo.join("src")).unwrap();
+    let config = r#"
+reset_color = false
+module_separator = ""
+
+[[module]]
+type = "Metrics"
+hide_numbers = true
+largest_file = { percent = true }
+"#;
+    let lines = |changed: &[usize]| {
+        (0..10)
+            .map(|line| match changed.contains(&line) {
+                true => format!("changed {line}\n"),
+                false => format!("line {line}\n"),
+            })
+            .collect::<String>()
+    };
+
+    jj(repo, &["git", "init"], "");
+    std::fs::write(repo.join("src/main.rs"), lines(&[])).unwrap();
+    let added = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        config,
+    );
+    jj(repo, &["new"], "");
+    std::fs::write(repo.join("src/main.rs"), lines(&[2, 5, 7])).unwrap();
+    std::fs::write(repo.join("notes.txt"), "one\n").unwrap();
+    let changed = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        config,
+    );
+
+    assert_eq!(added, "\u{1b}[0m\u{1b}[35mmain.rs");
+    assert_eq!(changed, "\u{1b}[0m\u{1b}[35mmain.rs 30%");
+}
+
+#[test]
+fn modules_are_only_rendered_when_the_working_copy_is_in_their_revset() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "wip: when"], "");
+    let prompt = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        r#"
+reset_color = false
+module_separator = "|"
+on_module_error = "Glyph"
+
+[[module]]
+type = "Commit"
+when = "description(glob:'wip:*')"
+
+[[module]]
+type = "Symbol"
+symbol = "!"
+when = "conflicts()"
+
+[[module]]
+type = "Symbol"
+symbol = "?"
+when = "nonexistent_function()"
+"#,
+    );
+
+    assert_eq!(prompt, "\u{1b}[0m\u{1b}[mwip: when|\u{1b}[31m⚠|");
+}
+
+#[test]
+fn hint_ignores_the_inherited_description() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = r#"
+reset_color = false
+module_separator = "|"
+
+[[module]]
+type = "Hint"
+
+[[module]]
+type = "Commit"
+show_previous_if_empty = true
+"#;
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "parent"], "");
+    jj(repo, &["new"], "");
+    std::fs::write(repo.join("file"), "change\n").unwrap();
+    let prompt = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        config,
+    );
+    let json = jj(
+        repo,
+        &[
+            "starship",
+            "prompt",
+            "--format",
+            "json",
+            "--starship-config",
+            "-",
+        ],
+        config,
+    );
+
+    assert_eq!(
+        prompt,
+        "\u{1b}[0m\u{1b}[90m→ jj describe|\u{1b}[0m\u{1b}[0m\u{1b}[mparent⇣|"
+    );
+    let data: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(data["commit"]["described"], false);
+    assert_eq!(data["commit"]["warnings"]["conflict"], false);
+    assert_eq!(data["commit"]["warnings"]["empty"], false);
+}
+
+#[test]
+fn ok_leaves_commit_data_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "ready"], "");
+    std::fs::write(repo.join("file"), "change\n").unwrap();
+    let json = jj(
+        repo,
+        &[
+            "starship",
+            "prompt",
+            "--format",
+            "json",
+            "--starship-config",
+            "-",
+        ],
+        "[[module]]\ntype = \"Ok\"\n",
+    );
+
+    let data: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(data["commit"]["described"], true);
+    assert_eq!(data["commit"]["desc"], serde_json::Value::Null);
+}
+
+#[test]
+fn hide_when_current_shows_the_next_bookmark_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let config = |hide_when_current: bool| {
+        format!(
+            r#"
+reset_color = false
+
+[[module]]
+type = "Bookmarks"
+hide_when_current = {hide_when_current}
+"#
+        )
+    };
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["describe", "-m", "base"], "");
+    jj(repo, &["bookmark", "create", "main", "-r", "@"], "");
+    jj(repo, &["new", "-m", "feature"], "");
+    jj(repo, &["bookmark", "create", "feature", "-r", "@"], "");
+    let shown = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        &config(false),
+    );
+    let hidden = jj(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        &config(true),
+    );
+
+    assert_eq!(shown, "\u{1b}[0m\u{1b}[35mfeature ");
+    assert_eq!(hidden, "\u{1b}[0m\u{1b}[35mmain⇡1 ");
+}
+
+#[test]
+fn path_walks_bookmarks_from_trunk() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let render = |config: &str| {
+        jj_with_env(
+            repo,
+            &["starship", "prompt", "--starship-config", "-"],
+            &format!("[[module]]\ntype = \"Path\"\n{config}"),
+            &[("NO_COLOR", "1")],
+        )
+    };
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["commit", "-m", "first"], "");
+    jj(repo, &["bookmark", "create", "main", "-r", "@-"], "");
+    jj(repo, &["commit", "-m", "second"], "");
+    jj(repo, &["bookmark", "create", "feature", "-r", "@-"], "");
+    jj(repo, &["commit", "-m", "third"], "");
+
+    assert_eq!(render(""), "main → feature → @+2 ");
+    assert_eq!(render("max_entries = 1\n"), "… → feature → @+2 ");
+}
+
+#[test]
+fn bookmarks_only_config_computes_no_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let collect = |module: &str| {
+        let json = jj(
+            repo,
+            &[
+                "starship",
+                "prompt",
+                "--format",
+                "json",
+                "--starship-config",
+                "-",
+            ],
+            &format!("[[module]]\ntype = \"{module}\"\n"),
+        );
+        serde_json::from_str::<serde_json::Value>(&json).unwrap()
+    };
+
+    jj(repo, &["git", "init"], "");
+    jj(repo, &["bookmark", "create", "main", "-r", "@"], "");
+    std::fs::write(repo.join("file"), "change\n").unwrap();
+    let bookmarks = collect("Bookmarks");
+    let metrics = collect("Metrics");
+
+    assert_eq!(bookmarks["bookmarks"]["bookmarks"][0]["name"], "main");
+    assert_eq!(bookmarks["commit"]["diff"], serde_json::Value::Null);
+    assert_eq!(
+        bookmarks["commit"]["warnings"]["empty"],
+        serde_json::Value::Null
+    );
+    assert_eq!(metrics["commit"]["diff"]["files_changed"], 1);
+}
+
+#[test]
+fn bookmark_base_diffs_every_commit_past_the_bookmark() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let diff = |base: &str| {
+        let json = jj(
+            repo,
+            &[
+                "starship",
+                "prompt",
+                "--format",
+                "json",
+                "--starship-config",
+                "-",
+            ],
+            &format!("[[module]]\ntype = \"Metrics\"\nbase = \"{base}\"\n"),
+        );
+        serde_json::from_str::<serde_json::Value>(&json).unwrap()["commit"]["diff"].clone()
+    };
+
+    jj(repo, &["git", "init"], "");
+    std::fs::write(repo.join("base"), "base\n").unwrap();
+    jj(repo, &["commit", "-m", "base"], "");
+    jj(repo, &["bookmark", "create", "main", "-r", "@-"], "");
+    std::fs::write(repo.join("first"), "one\ntwo\n").unwrap();
+    jj(repo, &["commit", "-m", "first"], "");
+    std::fs::write(repo.join("second"), "three\n").unwrap();
+    std::fs::write(repo.join("base"), "changed\n").unwrap();
+
+    assert_eq!(
+        diff("Bookmark"),
+        serde_json::json!({"files_changed": 3, "lines_added": 4, "lines_removed": 1, "largest_file": null})
+    );
+    assert_eq!(
+        diff("Parent"),
+        serde_json::json!({"files_changed": 2, "lines_added": 2, "lines_removed": 1, "largest_file": null})
+    );
+}
+
+#[test]
+fn outside_repo_text_is_rendered_outside_a_repo() {
+    let dir = tempfile::tempdir().unwrap();
+    let prompt = |config: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_starship-jj"))
+            .current_dir(dir.path())
+            .env("JJ_CONFIG", dir.path().join("no-config.toml"))
+            .env_remove("STARSHIP_JJ_CONFIG")
+            .args(["starship", "prompt", "--starship-config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(config.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let outside =
+        prompt("outside_repo_text = \"no-jj\"\n[outside_repo_style]\ncolor = \"BrightBlack\"\n");
+    let without_text = prompt("");
+
+    assert!(outside.status.success());
+    assert_eq!(
+        String::from_utf8(outside.stdout).unwrap(),
+        "\u{1b}[0m\u{1b}[90mno-jj\u{1b}[0m\u{1b}[0m\u{1b}[m"
+    );
+    assert!(!without_text.status.success());
+    assert!(
+        String::from_utf8_lossy(&without_text.stderr).contains("There is no jj repo"),
+        "{}",
+        String::from_utf8_lossy(&without_text.stderr)
+    );
+}
+
+#[test]
+fn notifications_survive_no_color() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    let cache = repo.join("cache");
+
+    jj(repo, &["git", "init"], "");
+    std::fs::write(repo.join("file"), "base").unwrap();
+    jj(repo, &["commit", "-m", "base"], "");
+    jj(repo, &["bookmark", "create", "base", "-r", "@-"], "");
+    std::fs::write(repo.join("file"), "one").unwrap();
+    jj(repo, &["commit", "-m", "one"], "");
+    jj(repo, &["bookmark", "create", "one", "-r", "@-"], "");
+    jj(repo, &["new", "base"], "");
+    std::fs::write(repo.join("file"), "two").unwrap();
+    jj(repo, &["commit", "-m", "two"], "");
+    jj(repo, &["new", "one", "@-"], "");
+    let prompt = jj_with_env(
+        repo,
+        &["starship", "prompt", "--starship-config", "-"],
+        r#"
+notify_on = ["Conflict"]
+
+[[module]]
+type = "Symbol"
+"#,
+        &[
+            ("NO_COLOR", "1"),
+            ("XDG_CACHE_HOME", cache.to_str().unwrap()),
+        ],
+    );
+
+    assert!(
+        prompt.ends_with("\u{1b}]9;jj: working copy has conflicts\u{7}"),
+        "{prompt:?}"
+    );
+    assert!(!prompt.contains("\u{1b}["), "{prompt:?}");
+}